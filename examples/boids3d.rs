@@ -0,0 +1,73 @@
+//! A 3D flock flying inside a wrapping box.
+//!
+//! `cargo run --release --example boids3d --features dim3`
+//!
+//! WASD moves the camera, Space/Shift go up and down, hold the right mouse button to look around.
+
+use bevy::prelude::*;
+use boid::{
+    fly_camera_system, random_direction3d, Boid3d, Boid3dPlugin, Bounds3d, FlyCamera, RngResource,
+};
+use rand::Rng;
+
+const BOID_COUNT: usize = 500;
+
+fn main() {
+    App::new()
+        .insert_resource(ClearColor(Color::rgb_u8(64, 18, 18)))
+        .add_plugins(DefaultPlugins)
+        .add_plugins(Boid3dPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, fly_camera_system)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut rng: ResMut<RngResource>,
+    bounds: Res<Bounds3d>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, bounds.half_extent.z * 3.0),
+            ..default()
+        },
+        FlyCamera::default(),
+    ));
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 1.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    let mesh = meshes.add(
+        shape::Capsule {
+            radius: 1.0,
+            depth: 3.0,
+            ..default()
+        }
+        .into(),
+    );
+    let material = materials.add(Color::rgb(0.9, 0.9, 1.0).into());
+    let half_extent = bounds.half_extent;
+    for _ in 0..BOID_COUNT {
+        let position = Vec3::new(
+            rng.0.gen_range(-half_extent.x..=half_extent.x),
+            rng.0.gen_range(-half_extent.y..=half_extent.y),
+            rng.0.gen_range(-half_extent.z..=half_extent.z),
+        );
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            Boid3d {
+                direction: random_direction3d(&mut rng.0),
+                ..default()
+            },
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+//! Benchmarks a crowded flock with and without `BoidConfig::max_neighbors`.
+//!
+//! `cargo run --release --example dense -- [boids] [frames] [max neighbors]`
+//!
+//! The boids are packed into a small world so each one sees hundreds of neighbors.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use boid::{BoidConfig, BoidPlugin, MaxBoids, SpawnConfig, WorldBounds};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let boids: usize = args
+        .next()
+        .map_or(3000, |arg| arg.parse().expect("boid count"));
+    let frames: usize = args
+        .next()
+        .map_or(300, |arg| arg.parse().expect("frame count"));
+    let max_neighbors: usize = args
+        .next()
+        .map_or(16, |arg| arg.parse().expect("neighbor cap"));
+
+    for cap in [None, Some(max_neighbors)] {
+        let elapsed = run(boids, frames, cap);
+        println!(
+            "{boids} boids, {frames} frames, max_neighbors {cap:?}: {:.2?} total, {:.3} ms/frame",
+            elapsed,
+            elapsed.as_secs_f64() * 1000.0 / frames as f64
+        );
+    }
+}
+
+fn run(boids: usize, frames: usize, max_neighbors: Option<usize>) -> Duration {
+    let bounds = WorldBounds {
+        min: Vec2::ZERO,
+        max: Vec2::splat(300.0),
+    };
+    let step = Duration::from_secs_f64(1.0 / 60.0);
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(FixedTime::new(step))
+        .insert_resource(bounds)
+        .insert_resource(BoidConfig {
+            max_neighbors,
+            ..default()
+        })
+        .insert_resource(SpawnConfig {
+            count: boids,
+            area: Rect::from_corners(bounds.min, bounds.max),
+            ..default()
+        })
+        .insert_resource(MaxBoids(boids))
+        .add_plugins(BoidPlugin);
+
+    // `ManualDuration` adds to the wall clock, so advance an explicit instant by one step instead
+    let mut now = Instant::now();
+    let mut update = |app: &mut App| {
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(now));
+        app.update();
+        now += step;
+    };
+
+    // the first update runs the startup systems
+    update(&mut app);
+
+    let start = Instant::now();
+    for _ in 0..frames {
+        update(&mut app);
+    }
+    start.elapsed()
+}
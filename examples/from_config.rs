@@ -0,0 +1,41 @@
+//! The default simulation with its parameters read from a TOML file.
+//!
+//! `cargo run --example from_config -- examples/boids.toml`, or set `BOID_CONFIG` to the path.
+//! Without a path, or if the file doesn't exist, the defaults are used.
+
+use std::process::ExitCode;
+
+use bevy::prelude::*;
+use boid::{spawn_boid, spawn_camera, BoidPlugin, BoidSettings, SpawnConfig};
+
+const BOID_COUNT: usize = 300;
+
+fn main() -> ExitCode {
+    let settings = match BoidSettings::path_from_env().map(BoidSettings::load) {
+        Some(Ok(settings)) => settings,
+        Some(Err(err)) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+        None => BoidSettings::default(),
+    };
+    let area = settings
+        .bounds
+        .map_or(SpawnConfig::default().area, |bounds| {
+            Rect::from_corners(bounds.min, bounds.max)
+        });
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins);
+    settings.insert_into(&mut app);
+    app.insert_resource(SpawnConfig {
+        count: BOID_COUNT,
+        area,
+        ..default()
+    })
+    .add_plugins(BoidPlugin)
+    .add_systems(Startup, spawn_camera)
+    .add_systems(Update, spawn_boid)
+    .run();
+    ExitCode::SUCCESS
+}
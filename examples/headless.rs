@@ -0,0 +1,68 @@
+//! Runs the simulation without a window or rendering and prints how long it took.
+//!
+//! `cargo run --release --example headless -- <boids> <frames> [grid cell size]`
+//!
+//! Passing a cell size switches from the KD-tree to the uniform grid backend.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use boid::{BoidPlugin, MaxBoids, SpatialBackend, SpawnConfig, WorldBounds};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let boids: usize = args
+        .next()
+        .map_or(1000, |arg| arg.parse().expect("boid count"));
+    let frames: usize = args
+        .next()
+        .map_or(600, |arg| arg.parse().expect("frame count"));
+    let backend = args
+        .next()
+        .map_or(SpatialBackend::KdTree, |arg| SpatialBackend::Grid {
+            cell_size: arg.parse().expect("grid cell size"),
+        });
+
+    let bounds = WorldBounds {
+        min: Vec2::ZERO,
+        max: Vec2::new(1280.0, 720.0),
+    };
+    let step = Duration::from_secs_f64(1.0 / 60.0);
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        // run exactly one 60Hz simulation step per update so runs are comparable
+        .insert_resource(FixedTime::new(step))
+        .insert_resource(bounds)
+        .insert_resource(backend)
+        .insert_resource(SpawnConfig {
+            count: boids,
+            area: Rect::from_corners(bounds.min, bounds.max),
+            ..default()
+        })
+        .insert_resource(MaxBoids(boids))
+        .add_plugins(BoidPlugin);
+
+    // `ManualDuration` adds to the wall clock, so advance an explicit instant by one step instead
+    let mut now = Instant::now();
+    let mut update = |app: &mut App| {
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(now));
+        app.update();
+        now += step;
+    };
+
+    // the first update runs the startup systems
+    update(&mut app);
+
+    let start = Instant::now();
+    for _ in 0..frames {
+        update(&mut app);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{boids} boids, {frames} frames, {backend:?}: {:.2?} total, {:.3} ms/frame",
+        elapsed,
+        elapsed.as_secs_f64() * 1000.0 / frames as f64
+    );
+}
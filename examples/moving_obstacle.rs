@@ -0,0 +1,63 @@
+//! A large obstacle sweeping back and forth across the flock, which scatters out of its way.
+//!
+//! `cargo run --release --example moving_obstacle`
+
+use bevy::prelude::*;
+use boid::{
+    draw_obstacles_system, spawn_camera, BoidPlugin, DebugDrawPlugin, Obstacle, ObstacleTool,
+    ObstacleVelocity, SpawnConfig, WorldBounds,
+};
+
+const BOIDS: usize = 300;
+const OBSTACLE_RADIUS: f32 = 60.0;
+const OBSTACLE_SPEED: f32 = 150.0;
+
+fn main() {
+    App::new()
+        .insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.15)))
+        .add_plugins(DefaultPlugins)
+        .insert_resource(SpawnConfig {
+            count: BOIDS,
+            ..default()
+        })
+        .add_plugins(DebugDrawPlugin)
+        .add_plugins(BoidPlugin)
+        // only for `draw_obstacles_system`, the tool itself stays off
+        .init_resource::<ObstacleTool>()
+        .add_systems(Startup, spawn_camera)
+        .add_systems(Startup, spawn_obstacle)
+        .add_systems(Update, bounce_obstacle_system)
+        .add_systems(Update, draw_obstacles_system)
+        .run();
+}
+
+fn spawn_obstacle(mut commands: Commands, bounds: Res<WorldBounds>) {
+    let start = Vec2::new(bounds.min.x, (bounds.min.y + bounds.max.y) / 2.0);
+    commands.spawn((
+        TransformBundle::from_transform(Transform::from_translation(start.extend(0.0))),
+        Obstacle {
+            radius: OBSTACLE_RADIUS,
+        },
+        ObstacleVelocity(Vec2::new(OBSTACLE_SPEED, OBSTACLE_SPEED / 3.0)),
+    ));
+}
+
+/// Turns the obstacle around whenever it reaches an edge of the world.
+fn bounce_obstacle_system(
+    bounds: Res<WorldBounds>,
+    mut obstacle_query: Query<(&Transform, &mut ObstacleVelocity)>,
+) {
+    for (transform, mut velocity) in obstacle_query.iter_mut() {
+        let position = transform.translation;
+        if (position.x <= bounds.min.x && velocity.0.x < 0.0)
+            || (position.x >= bounds.max.x && velocity.0.x > 0.0)
+        {
+            velocity.0.x = -velocity.0.x;
+        }
+        if (position.y <= bounds.min.y && velocity.0.y < 0.0)
+            || (position.y >= bounds.max.y && velocity.0.y > 0.0)
+        {
+            velocity.0.y = -velocity.0.y;
+        }
+    }
+}
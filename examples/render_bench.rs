@@ -0,0 +1,92 @@
+//! Compares the frame time of drawing a large flock with sprites and with `InstancedBoidPlugin`.
+//!
+//! `cargo run --release --example render_bench --features instanced_rendering -- [boids] [frames]
+//! [sprites|instanced]`
+//!
+//! Runs with vsync off and exits after `frames` frames, printing the average frame time. Run it
+//! once per mode, the numbers depend entirely on the machine's CPU and GPU.
+
+use std::time::{Duration, Instant};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::PresentMode;
+use boid::{spawn_camera, BoidPlugin, InstancedBoidPlugin, MaxBoids, SpawnConfig};
+
+/// Frames skipped before measuring, while assets load and the pipelines compile.
+const WARMUP_FRAMES: usize = 60;
+
+#[derive(Resource)]
+struct Bench {
+    mode: String,
+    boids: usize,
+    frames: usize,
+    seen: usize,
+    start: Option<Instant>,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let boids: usize = args
+        .next()
+        .map_or(10_000, |arg| arg.parse().expect("boid count"));
+    let frames: usize = args
+        .next()
+        .map_or(600, |arg| arg.parse().expect("frame count"));
+    let mode = args.next().unwrap_or_else(|| "instanced".to_owned());
+    assert!(
+        mode == "sprites" || mode == "instanced",
+        "mode is sprites or instanced, not {mode}"
+    );
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            present_mode: PresentMode::AutoNoVsync,
+            ..default()
+        }),
+        ..default()
+    }))
+    .insert_resource(SpawnConfig {
+        count: boids,
+        ..default()
+    })
+    .insert_resource(MaxBoids(boids))
+    .add_plugins(BoidPlugin)
+    .add_systems(Startup, spawn_camera)
+    .add_systems(Update, measure_system);
+    if mode == "instanced" {
+        app.add_plugins(InstancedBoidPlugin);
+    }
+    app.insert_resource(Bench {
+        mode,
+        boids,
+        frames,
+        seen: 0,
+        start: None,
+    })
+    .run();
+}
+
+fn measure_system(mut bench: ResMut<Bench>, mut exit: EventWriter<AppExit>) {
+    bench.seen += 1;
+    if bench.seen == WARMUP_FRAMES {
+        bench.start = Some(Instant::now());
+    }
+    let Some(start) = bench.start else {
+        return;
+    };
+    let measured = bench.seen - WARMUP_FRAMES;
+    if measured < bench.frames {
+        return;
+    }
+    let elapsed: Duration = start.elapsed();
+    println!(
+        "{}: {} boids, {} frames, {:.3} ms/frame",
+        bench.mode,
+        bench.boids,
+        measured,
+        elapsed.as_secs_f64() * 1000.0 / measured as f64
+    );
+    exit.send(AppExit);
+}
@@ -0,0 +1,137 @@
+//! Two flocks with different `BoidConfig`s side by side: a cohesive one on the left and a
+//! scattered one on the right.
+//!
+//! `cargo run --release --example side_by_side`
+//!
+//! Each flock runs `BoidPlugin` in a `SubApp` with its own `World`, so every resource the
+//! systems read, from `BoidConfig` to the spatial tree, exists once per flock and the two never
+//! see each other. Every frame the main app copies the boids' transforms onto sprites, shifting
+//! the right flock over by half the window.
+
+use bevy::app::{AppLabel, SubApp};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use boid::{
+    spawn_camera, Boid, BoidConfig, BoidPlugin, RngResource, SpawnConfig, WorldBounds,
+    DEFAULT_BOID_SPRITE,
+};
+
+const BOIDS_PER_FLOCK: usize = 200;
+/// Size of each half of bevy's default window.
+const HALF: Vec2 = Vec2::new(640.0, 720.0);
+
+#[derive(AppLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Flock {
+    Cohesive,
+    Scattered,
+}
+
+/// The image both flocks are drawn with.
+#[derive(Resource)]
+struct BoidTexture(Handle<Image>);
+
+/// Sprite in the main world drawing each boid of a flock, kept in the flock's `World`.
+#[derive(Resource, Default)]
+struct Mirrors(HashMap<Entity, Entity>);
+
+fn main() {
+    let cohesive = BoidConfig {
+        cohesion: 1.0,
+        alignment: 0.5,
+        separation: 0.3,
+        ..default()
+    };
+    let scattered = BoidConfig {
+        cohesion: 0.0,
+        alignment: 0.05,
+        separation: 1.0,
+        wander: 0.5,
+        ..default()
+    };
+
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.15)))
+        .add_plugins(DefaultPlugins)
+        .add_systems(Startup, spawn_camera)
+        .add_systems(Startup, setup);
+    app.insert_sub_app(Flock::Cohesive, flock(cohesive, 0.0));
+    app.insert_sub_app(Flock::Scattered, flock(scattered, HALF.x));
+    app.run();
+}
+
+/// A headless app simulating one flock in a `HALF` sized world, drawn `offset` to the right.
+fn flock(config: BoidConfig, offset: f32) -> SubApp {
+    let bounds = WorldBounds {
+        min: Vec2::ZERO,
+        max: HALF,
+    };
+    let mut flock = App::new();
+    flock
+        .add_plugins(MinimalPlugins)
+        .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
+        .insert_resource(config)
+        .insert_resource(bounds)
+        .insert_resource(SpawnConfig {
+            count: BOIDS_PER_FLOCK,
+            area: Rect::from_corners(bounds.min, bounds.max),
+            ..default()
+        })
+        // the same seed for both, so they start from the same layout
+        .insert_resource(RngResource::seeded(0))
+        .init_resource::<Mirrors>()
+        .add_plugins(BoidPlugin);
+    SubApp::new(flock, move |main_world, flock| {
+        mirror(main_world, &mut flock.world, offset)
+    })
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BoidTexture(asset_server.load(DEFAULT_BOID_SPRITE)));
+    // the border between the two halves
+    commands.spawn(SpriteBundle {
+        transform: Transform::from_xyz(HALF.x, HALF.y / 2.0, 1.0),
+        sprite: Sprite {
+            color: Color::GRAY,
+            custom_size: Some(Vec2::new(2.0, HALF.y)),
+            ..default()
+        },
+        ..default()
+    });
+}
+
+/// Moves a sprite in `main_world` onto every boid of `flock`, spawning and despawning sprites as
+/// boids come and go.
+fn mirror(main_world: &mut World, flock: &mut World, offset: f32) {
+    let Some(texture) = main_world.get_resource::<BoidTexture>() else {
+        return;
+    };
+    let texture = texture.0.clone();
+    let boids: HashMap<Entity, Transform> = flock
+        .query_filtered::<(Entity, &Transform), With<Boid>>()
+        .iter(flock)
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+
+    let mut mirrors = flock.resource_mut::<Mirrors>();
+    mirrors.0.retain(|boid, sprite| {
+        let alive = boids.contains_key(boid);
+        if !alive {
+            main_world.despawn(*sprite);
+        }
+        alive
+    });
+    for (boid, mut transform) in boids {
+        transform.translation.x += offset;
+        match mirrors.0.get(&boid) {
+            Some(sprite) => *main_world.get_mut::<Transform>(*sprite).unwrap() = transform,
+            None => {
+                let sprite = main_world.spawn(SpriteBundle {
+                    transform,
+                    texture: texture.clone(),
+                    ..default()
+                });
+                mirrors.0.insert(boid, sprite.id());
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Two species sharing the screen: tight red flocks and loose, spread out blue ones drawn with
+//! their own sprite.
+//!
+//! `cargo run --release --example species`
+
+use bevy::prelude::*;
+use boid::{
+    spawn_camera, Boid, BoidPlugin, BoidSpriteHandles, BoidSprites, RngResource, SpeciesConfig,
+    SpeciesProfile,
+};
+use rand::Rng;
+
+const BOIDS_PER_SPECIES: usize = 150;
+const RED: u32 = 0;
+const BLUE: u32 = 1;
+
+fn main() {
+    let species = SpeciesConfig::default()
+        .with_profile(
+            RED,
+            SpeciesProfile {
+                cohesion: 0.8,
+                alignment: 0.3,
+                separation: 0.2,
+                view_distance: 70.0,
+                separation_distance: 1.0,
+            },
+        )
+        .with_profile(
+            BLUE,
+            SpeciesProfile {
+                cohesion: 0.05,
+                alignment: 0.2,
+                separation: 1.2,
+                view_distance: 40.0,
+                separation_distance: 12.0,
+            },
+        );
+
+    App::new()
+        .insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.15)))
+        .add_plugins(DefaultPlugins)
+        .insert_resource(species)
+        .insert_resource(BoidSprites::default().with_species(BLUE, "sprites/boid.png"))
+        .add_plugins(BoidPlugin)
+        .add_systems(Startup, spawn_camera)
+        .add_systems(Startup, spawn_species)
+        .run();
+}
+
+fn spawn_species(
+    mut commands: Commands,
+    sprites: Res<BoidSpriteHandles>,
+    mut rng: ResMut<RngResource>,
+    window_query: Query<&Window>,
+) {
+    let window = window_query.single();
+    for (species, color) in [(RED, Color::RED), (BLUE, Color::CYAN)] {
+        for _ in 0..BOIDS_PER_SPECIES {
+            let position = Vec2::new(
+                rng.0.gen_range(0.0..window.width()),
+                rng.0.gen_range(0.0..window.height()),
+            );
+            let angle = rng.0.gen_range(0.0..std::f32::consts::TAU);
+            commands.spawn((
+                SpriteBundle {
+                    transform: Transform::from_translation(position.extend(0.0)),
+                    texture: sprites.get(species),
+                    sprite: Sprite { color, ..default() },
+                    ..default()
+                },
+                Boid {
+                    direction: Vec2::from_angle(angle),
+                    species,
+                    ..default()
+                },
+            ));
+        }
+    }
+}
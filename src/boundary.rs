@@ -0,0 +1,323 @@
+use bevy::math::Vec3Swizzles;
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{current_speed, steer_boid, Boid};
+
+/// What happens to a boid that reaches an edge of the `WorldBounds`.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoundaryMode {
+    /// Teleport to the opposite edge.
+    #[default]
+    Wrap,
+    /// Steer back toward the interior once within `margin` of an edge.
+    Avoid,
+    /// Reflect the direction across the wall normal.
+    Bounce,
+}
+
+/// The `BoundaryMode` of each edge of the `WorldBounds`, e.g. wrapping left and right but
+/// bouncing off the top and bottom.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BoundarySides {
+    pub left: BoundaryMode,
+    pub right: BoundaryMode,
+    pub top: BoundaryMode,
+    pub bottom: BoundaryMode,
+}
+
+impl BoundarySides {
+    /// Every side in `mode`.
+    pub fn all(mode: BoundaryMode) -> Self {
+        Self {
+            left: mode,
+            right: mode,
+            top: mode,
+            bottom: mode,
+        }
+    }
+
+    /// Wrap on the left and right, `vertical` on the top and bottom.
+    pub fn horizontal_wrap(vertical: BoundaryMode) -> Self {
+        Self {
+            top: vertical,
+            bottom: vertical,
+            ..Self::all(BoundaryMode::Wrap)
+        }
+    }
+
+    /// Per axis, whether the sides at both ends wrap, so the world has a seam across that axis
+    /// rather than a wall.
+    pub fn wrapping(&self) -> BVec2 {
+        BVec2::new(
+            self.left == BoundaryMode::Wrap && self.right == BoundaryMode::Wrap,
+            self.bottom == BoundaryMode::Wrap && self.top == BoundaryMode::Wrap,
+        )
+    }
+
+    /// The modes at the low and the high end of the x (0) or y (1) axis.
+    fn axis(&self, axis: usize) -> (BoundaryMode, BoundaryMode) {
+        if axis == 0 {
+            (self.left, self.right)
+        } else {
+            (self.bottom, self.top)
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BoundaryConfig {
+    pub sides: BoundarySides,
+    /// Distance from an edge at which `BoundaryMode::Avoid` starts steering a standing boid.
+    pub margin: f32,
+    /// Seconds of travel added to `margin`, so a boid twice as fast starts turning that much
+    /// earlier instead of overshooting the margin before it can turn.
+    pub lookahead: f32,
+    pub avoid_strength: f32,
+    /// Let boids see neighbors across the edges of an axis both of whose sides wrap, so a flock
+    /// can span the seam. Flat queries only see what is on the same side.
+    pub wrap_neighbors: bool,
+}
+
+impl Default for BoundaryConfig {
+    fn default() -> Self {
+        Self {
+            sides: BoundarySides::default(),
+            margin: 50.0,
+            lookahead: 0.5,
+            avoid_strength: 1.0,
+            wrap_neighbors: true,
+        }
+    }
+}
+
+/// The area boids are kept in, independent of the window so the world can be larger or smaller
+/// than the viewport.
+///
+/// Insert it before adding [`crate::BoidPlugin`] to override it, otherwise it is set to the primary
+/// window's size at startup (or `Default` when running without a window).
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct WorldBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for WorldBounds {
+    /// The size of bevy's default window.
+    fn default() -> Self {
+        Self {
+            min: Vec2::ZERO,
+            max: Vec2::new(1280.0, 720.0),
+        }
+    }
+}
+
+impl WorldBounds {
+    /// Bounds matching the window, with the bottom left corner at the origin.
+    pub fn from_window(window: &Window) -> Self {
+        Self {
+            min: Vec2::ZERO,
+            max: Vec2::new(window.width(), window.height()),
+        }
+    }
+}
+
+/// Inserts `WorldBounds` sized to the primary window unless the app already provides one.
+pub fn init_world_bounds(
+    mut commands: Commands,
+    bounds: Option<Res<WorldBounds>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+) {
+    if bounds.is_some() {
+        return;
+    }
+    let bounds = window_query
+        .get_single()
+        .map(WorldBounds::from_window)
+        .unwrap_or_default();
+    commands.insert_resource(bounds);
+}
+
+pub fn avoid_walls_system(
+    mut boid_query: Query<(&mut Transform, &mut Boid)>,
+    config: Res<BoundaryConfig>,
+    bounds: Res<WorldBounds>,
+) {
+    // the edge helpers work in a space with the bottom left corner at the origin
+    let size = bounds.max - bounds.min;
+    let sides = &config.sides;
+    for (mut transform, mut boid) in boid_query.iter_mut() {
+        let start = transform.translation.xy() - bounds.min;
+        let position = wrap_position(start, size, sides);
+
+        let margin = config.margin + current_speed(&boid) * config.lookahead;
+        let steering = wall_avoidance(position, size, margin, sides);
+        let proximity = steering.length();
+        if proximity > 0.0 {
+            let weight = boid.rotation_speed * config.avoid_strength * proximity;
+            steer_boid(&mut boid, steering, weight);
+        }
+
+        let (direction, velocity) = bounce(position, size, boid.direction, boid.velocity, sides);
+        boid.direction = direction;
+        boid.velocity = velocity;
+        let position = clamp_to_walls(position, size, sides);
+
+        if position != start {
+            let [x, y] = (position + bounds.min).to_array();
+            transform.translation = Vec3::new(x, y, 0.0);
+        }
+    }
+}
+
+/// Moves a boid past a `Wrap` side to the opposite edge.
+fn wrap_position(mut position: Vec2, size: Vec2, sides: &BoundarySides) -> Vec2 {
+    for axis in 0..2 {
+        let (low, high) = sides.axis(axis);
+        if position[axis] < 0.0 && low == BoundaryMode::Wrap {
+            position[axis] = size[axis];
+        } else if position[axis] > size[axis] && high == BoundaryMode::Wrap {
+            position[axis] = 0.0;
+        }
+    }
+    position
+}
+
+/// Vector pointing back toward the interior from the `Avoid` sides, each axis grows from 0 at
+/// `margin` to 1 at the edge.
+fn wall_avoidance(position: Vec2, size: Vec2, margin: f32, sides: &BoundarySides) -> Vec2 {
+    if margin <= 0.0 {
+        return Vec2::ZERO;
+    }
+    let push = |axis: usize| {
+        let (low, high) = sides.axis(axis);
+        let (pos, max) = (position[axis], size[axis]);
+        if pos < margin && low == BoundaryMode::Avoid {
+            (margin - pos) / margin
+        } else if pos > max - margin && high == BoundaryMode::Avoid {
+            -(pos - (max - margin)) / margin
+        } else {
+            0.0
+        }
+    };
+    Vec2::new(push(0), push(1))
+}
+
+/// Flips the direction and velocity components that point out through a `Bounce` wall the boid
+/// touches.
+///
+/// Only outward components are flipped, otherwise a boid past the edge would flip every frame.
+fn bounce(
+    position: Vec2,
+    size: Vec2,
+    mut direction: Vec2,
+    mut velocity: Vec2,
+    sides: &BoundarySides,
+) -> (Vec2, Vec2) {
+    for axis in 0..2 {
+        let (low, high) = sides.axis(axis);
+        let out_low = position[axis] <= 0.0 && direction[axis] < 0.0;
+        let out_high = position[axis] >= size[axis] && direction[axis] > 0.0;
+        if (out_low && low == BoundaryMode::Bounce) || (out_high && high == BoundaryMode::Bounce) {
+            direction[axis] = -direction[axis];
+            velocity[axis] = -velocity[axis];
+        }
+    }
+    (direction, velocity)
+}
+
+/// Keeps a boid from ending up past a `Bounce` wall.
+fn clamp_to_walls(mut position: Vec2, size: Vec2, sides: &BoundarySides) -> Vec2 {
+    for axis in 0..2 {
+        let (low, high) = sides.axis(axis);
+        if low == BoundaryMode::Bounce {
+            position[axis] = position[axis].max(0.0);
+        }
+        if high == BoundaryMode::Bounce {
+            position[axis] = position[axis].min(size[axis]);
+        }
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeScale;
+
+    #[test]
+    fn fast_boids_start_avoiding_walls_earlier() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
+            .init_resource::<TimeScale>()
+            .init_resource::<WorldBounds>()
+            .insert_resource(BoundaryConfig {
+                sides: BoundarySides::all(BoundaryMode::Avoid),
+                ..default()
+            })
+            .add_systems(Update, avoid_walls_system);
+        // both heading for the left wall from just past the plain margin
+        let heading = Vec2::new(-1.0, 1.0).normalize();
+        let mut spawn = |speed: f32| {
+            app.world
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(60.0, 360.0, 0.0)),
+                    Boid {
+                        direction: heading,
+                        velocity: heading * speed,
+                        ..default()
+                    },
+                ))
+                .id()
+        };
+        let slow = spawn(10.0);
+        let fast = spawn(40.0);
+        app.update();
+
+        let steering = |entity| app.world.get::<Boid>(entity).unwrap().steering;
+        assert_eq!(steering(slow), Vec2::ZERO);
+        assert!(steering(fast).x > 0.0, "{}", steering(fast));
+    }
+
+    #[test]
+    fn sides_can_wrap_and_bounce_at_once() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
+            .init_resource::<TimeScale>()
+            .init_resource::<WorldBounds>()
+            .insert_resource(BoundaryConfig {
+                sides: BoundarySides::horizontal_wrap(BoundaryMode::Bounce),
+                ..default()
+            })
+            .add_systems(Update, avoid_walls_system);
+        let heading = Vec2::new(1.0, 1.0).normalize();
+        let mut spawn = |x: f32, y: f32| {
+            app.world
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(x, y, 0.0)),
+                    Boid {
+                        direction: heading,
+                        velocity: heading * 20.0,
+                        ..default()
+                    },
+                ))
+                .id()
+        };
+        let past_right = spawn(1285.0, 360.0);
+        let past_top = spawn(640.0, 725.0);
+        app.update();
+
+        let state = |entity| {
+            let position = app.world.get::<Transform>(entity).unwrap().translation.xy();
+            (position, app.world.get::<Boid>(entity).unwrap().direction)
+        };
+        // out the right and back in on the left, still heading the same way
+        assert_eq!(state(past_right), (Vec2::new(0.0, 360.0), heading));
+        // held at the top and turned back down
+        assert_eq!(
+            state(past_top),
+            (Vec2::new(640.0, 720.0), Vec2::new(heading.x, -heading.y))
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use crate::{DebugDraw, WorldBounds};
+
+const OUTLINE_COLOR: Color = Color::WHITE;
+/// A little lighter than the window's clear color, so the world stands out when zoomed out.
+const BACKGROUND_COLOR: Color = Color::rgb(0.6, 0.6, 0.95);
+/// Behind the boids, obstacles and everything else drawn at zero.
+const BACKGROUND_Z: f32 = -10.0;
+
+/// Outlines `WorldBounds` and fills it with a background sprite, shown by default. B toggles it.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct BoundsView {
+    pub visible: bool,
+    /// Color of the sprite covering the world, `None` only draws the outline.
+    pub background: Option<Color>,
+}
+
+impl Default for BoundsView {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            background: Some(BACKGROUND_COLOR),
+        }
+    }
+}
+
+/// The sprite `world_background_system` keeps covering `WorldBounds`.
+#[derive(Component)]
+pub struct WorldBackground;
+
+pub fn toggle_bounds_view_system(mut view: ResMut<BoundsView>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::B) {
+        view.visible = !view.visible;
+    }
+}
+
+pub fn draw_world_bounds_system(
+    view: Res<BoundsView>,
+    bounds: Res<WorldBounds>,
+    mut lines: DebugDraw,
+) {
+    if !view.visible {
+        return;
+    }
+    let corners = [
+        bounds.min,
+        Vec2::new(bounds.max.x, bounds.min.y),
+        bounds.max,
+        Vec2::new(bounds.min.x, bounds.max.y),
+    ];
+    for (i, corner) in corners.iter().enumerate() {
+        let next = corners[(i + 1) % corners.len()];
+        lines.line_colored(corner.extend(0.0), next.extend(0.0), OUTLINE_COLOR);
+    }
+}
+
+/// Spawns the `WorldBackground` sprite and keeps its size, color and visibility in line with
+/// `WorldBounds` and `BoundsView`.
+pub fn world_background_system(
+    mut commands: Commands,
+    view: Res<BoundsView>,
+    bounds: Res<WorldBounds>,
+    mut background_query: Query<
+        (&mut Sprite, &mut Transform, &mut Visibility),
+        With<WorldBackground>,
+    >,
+) {
+    let center = ((bounds.min + bounds.max) / 2.0).extend(BACKGROUND_Z);
+    let size = bounds.max - bounds.min;
+    let visibility = match view.background {
+        Some(_) if view.visible => Visibility::Inherited,
+        _ => Visibility::Hidden,
+    };
+    let color = view.background.unwrap_or(BACKGROUND_COLOR);
+    let Ok((mut sprite, mut transform, mut shown)) = background_query.get_single_mut() else {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(center),
+                visibility,
+                ..default()
+            },
+            WorldBackground,
+        ));
+        return;
+    };
+    if !view.is_changed() && !bounds.is_changed() {
+        return;
+    }
+    sprite.color = color;
+    sprite.custom_size = Some(size);
+    transform.translation = center;
+    *shown = visibility;
+}
@@ -0,0 +1,75 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+const PAN_SPEED: f32 = 500.0;
+/// Fraction the view shrinks or grows per scroll line.
+const ZOOM_STEP: f32 = 0.1;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+
+/// WASD pans the 2D camera and the scroll wheel zooms it.
+///
+/// Panning speed is scaled by the zoom so it feels the same on screen at any zoom level.
+pub fn camera_control_system(
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    keys: Res<Input<KeyCode>>,
+    mut scroll: EventReader<MouseWheel>,
+    time: Res<Time>,
+) {
+    let scrolled: f32 = scroll
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            // roughly one line per 100 pixels on touchpads
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+
+    let mut pan = Vec2::ZERO;
+    for (key, direction) in [
+        (KeyCode::W, Vec2::Y),
+        (KeyCode::A, Vec2::NEG_X),
+        (KeyCode::S, Vec2::NEG_Y),
+        (KeyCode::D, Vec2::X),
+    ] {
+        if keys.pressed(key) {
+            pan += direction;
+        }
+    }
+
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        if scrolled != 0.0 {
+            projection.scale =
+                (projection.scale * (1.0 - scrolled * ZOOM_STEP)).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+        let offset = pan.normalize_or_zero() * PAN_SPEED * projection.scale * time.delta_seconds();
+        transform.translation += offset.extend(0.0);
+    }
+}
+
+/// World position under the cursor, `None` while the cursor is outside the window.
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+/// The cursor in world space, as seen through the primary window's 2D camera.
+#[derive(SystemParam)]
+pub struct WorldCursor<'w, 's> {
+    window_query: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    camera_query: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera2d>>,
+}
+
+impl WorldCursor<'_, '_> {
+    /// `None` while the cursor is outside the window, or without exactly one window and camera.
+    pub fn position(&self) -> Option<Vec2> {
+        let window = self.window_query.get_single().ok()?;
+        let (camera, camera_transform) = self.camera_query.get_single().ok()?;
+        cursor_world_position(window, camera, camera_transform)
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{Boid, SpatialQuery};
+
+/// Seconds between clustering passes, a pass queries the neighbors of every boid.
+const CLUSTERING_INTERVAL: f32 = 1.0;
+
+/// Splits the flock into separate flocks every `FlockClustering::interval` seconds, tagging each
+/// boid with a `FlockId` and keeping `FlockClustering::count` up to date.
+#[derive(Default)]
+pub struct FlockClusteringPlugin;
+
+impl Plugin for FlockClusteringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlockClustering>()
+            .add_systems(Update, flock_clustering_system);
+    }
+}
+
+/// The flock a boid was found in by the last clustering pass. Boids chained together by being
+/// within `Boid::view_distance` of one another share an id.
+///
+/// Ids are numbered from zero on every pass, so a flock can get a different id after it splits
+/// or merges with another one.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlockId(pub u32);
+
+#[derive(Resource)]
+pub struct FlockClustering {
+    /// Number of distinct flocks found by the last pass, a lone boid counts as a flock of one.
+    pub count: usize,
+    timer: Timer,
+}
+
+impl Default for FlockClustering {
+    fn default() -> Self {
+        Self::every(CLUSTERING_INTERVAL)
+    }
+}
+
+impl FlockClustering {
+    /// Clusters every `seconds`, starting with the first update.
+    pub fn every(seconds: f32) -> Self {
+        let mut timer = Timer::from_seconds(seconds, TimerMode::Repeating);
+        timer.set_elapsed(timer.duration());
+        Self { count: 0, timer }
+    }
+
+    pub fn interval(&self) -> f32 {
+        self.timer.duration().as_secs_f32()
+    }
+}
+
+/// Finds the connected components of the neighbor graph, where each boid is connected to the
+/// boids within its `view_distance`, and gives every boid the `FlockId` of its component.
+pub fn flock_clustering_system(
+    mut commands: Commands,
+    mut clustering: ResMut<FlockClustering>,
+    time: Res<Time>,
+    spatial: SpatialQuery,
+    boid_query: Query<(Entity, &Transform, &Boid, Option<&FlockId>)>,
+) {
+    if !clustering.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let index: HashMap<Entity, usize> = boid_query
+        .iter()
+        .enumerate()
+        .map(|(i, (entity, ..))| (entity, i))
+        .collect();
+    let mut flocks = DisjointSets::new(index.len());
+    for (i, (_, transform, boid, _)) in boid_query.iter().enumerate() {
+        let neighbors = spatial.within_distance(transform.translation.xy(), boid.view_distance);
+        for (_, neighbor) in neighbors {
+            if let Some(&j) = neighbor.and_then(|neighbor| index.get(&neighbor)) {
+                flocks.union(i, j);
+            }
+        }
+    }
+
+    let mut ids = HashMap::new();
+    for (i, (entity, _, _, current)) in boid_query.iter().enumerate() {
+        let next = ids.len() as u32;
+        let id = FlockId(*ids.entry(flocks.find(i)).or_insert(next));
+        if current != Some(&id) {
+            commands.entity(entity).insert(id);
+        }
+    }
+    clustering.count = ids.len();
+}
+
+/// Union-find over `0..len`, with path halving and union by size.
+struct DisjointSets {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (small, large) = if self.sizes[a] < self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoidPlugin, SpatialBackend, WorldBounds};
+
+    #[test]
+    fn separate_groups_get_separate_flock_ids() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(WorldBounds::default())
+            .insert_resource(SpatialBackend::Grid { cell_size: 50.0 })
+            .add_plugins(BoidPlugin)
+            .add_plugins(FlockClusteringPlugin);
+        // two chains of boids 30 apart, within each other's view distance of 50 but 400 away
+        // from the other chain
+        let mut spawn = |x: f32, y: f32| {
+            app.world
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(x, y, 0.0)),
+                    Boid::default(),
+                ))
+                .id()
+        };
+        let left: Vec<Entity> = (0..4)
+            .map(|i| spawn(200.0 + i as f32 * 30.0, 360.0))
+            .collect();
+        let right: Vec<Entity> = (0..4)
+            .map(|i| spawn(700.0, 200.0 + i as f32 * 30.0))
+            .collect();
+        app.update();
+
+        assert_eq!(app.world.resource::<FlockClustering>().count, 2);
+        let id = |entity| *app.world.get::<FlockId>(entity).unwrap();
+        assert!(left.iter().all(|&boid| id(boid) == id(left[0])));
+        assert!(right.iter().all(|&boid| id(boid) == id(right[0])));
+        assert_ne!(id(left[0]), id(right[0]));
+    }
+}
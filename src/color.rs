@@ -0,0 +1,104 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::Boid;
+
+/// Sprite color of a freshly spawned boid and of every boid in `BoidColorMode::Plain`.
+pub const BOID_COLOR: Color = Color::WHITE;
+const SLOW_COLOR: Color = Color::rgb(0.1, 0.3, 1.0);
+const FAST_COLOR: Color = Color::rgb(1.0, 0.2, 0.1);
+/// Colors closer than this in every channel are not written, to skip pointless sprite updates.
+const COLOR_EPSILON: f32 = 0.01;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoidColorMode {
+    #[default]
+    Plain,
+    /// Hue follows the heading angle around the color wheel.
+    Heading,
+    /// Blends from blue at `min_speed` to red at `max_speed`.
+    Speed,
+}
+
+impl BoidColorMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Plain => Self::Heading,
+            Self::Heading => Self::Speed,
+            Self::Speed => Self::Plain,
+        }
+    }
+}
+
+/// Cycles the `BoidColorMode` with `C`.
+pub fn cycle_color_mode_system(mut mode: ResMut<BoidColorMode>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::C) {
+        *mode = mode.next();
+    }
+}
+
+/// Tints each boid sprite according to the `BoidColorMode`.
+///
+/// The sprite is only written when its color changes noticeably, so boids holding a steady
+/// course don't trigger change detection or extra work in the renderer.
+pub fn color_boid_system(mode: Res<BoidColorMode>, mut boid_query: Query<(&Boid, &mut Sprite)>) {
+    for (boid, mut sprite) in boid_query.iter_mut() {
+        let color = boid_color(*mode, boid);
+        if color_changed(sprite.color, color) {
+            sprite.color = color;
+        }
+    }
+}
+
+/// The color `mode` gives `boid`.
+pub fn boid_color(mode: BoidColorMode, boid: &Boid) -> Color {
+    match mode {
+        BoidColorMode::Plain => BOID_COLOR,
+        BoidColorMode::Heading => {
+            heading_color(boid.velocity.try_normalize().unwrap_or(boid.direction))
+        }
+        BoidColorMode::Speed => speed_color(boid.velocity.length(), boid.min_speed, boid.max_speed),
+    }
+}
+
+fn heading_color(heading: Vec2) -> Color {
+    let angle = heading.y.atan2(heading.x);
+    let hue = (angle + PI) / (2.0 * PI) * 360.0;
+    Color::hsl(hue, 0.8, 0.5)
+}
+
+fn speed_color(speed: f32, min_speed: f32, max_speed: f32) -> Color {
+    let t = if max_speed > min_speed {
+        ((speed - min_speed) / (max_speed - min_speed)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let slow = Vec4::from_array(SLOW_COLOR.as_rgba_f32());
+    let fast = Vec4::from_array(FAST_COLOR.as_rgba_f32());
+    let [r, g, b, a] = slow.lerp(fast, t).to_array();
+    Color::rgba(r, g, b, a)
+}
+
+fn color_changed(current: Color, new: Color) -> bool {
+    let current = Vec4::from_array(current.as_rgba_f32());
+    let new = Vec4::from_array(new.as_rgba_f32());
+    (current - new).abs().max_element() > COLOR_EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_color_is_clamped_to_the_speed_range() {
+        assert!(!color_changed(speed_color(0.0, 10.0, 40.0), SLOW_COLOR));
+        assert!(!color_changed(speed_color(100.0, 10.0, 40.0), FAST_COLOR));
+    }
+
+    #[test]
+    fn small_color_changes_are_ignored() {
+        assert!(!color_changed(Color::WHITE, Color::rgb(0.995, 1.0, 1.0)));
+        assert!(color_changed(Color::WHITE, SLOW_COLOR));
+    }
+}
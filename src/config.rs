@@ -0,0 +1,419 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use toml_edit::{Document, Item, Value};
+
+use crate::flocking::CohesionWeighting;
+use crate::{BoidConfig, SpawnParams, WorldBounds};
+
+/// Environment variable naming the settings file when no path is passed on the command line.
+pub const CONFIG_ENV_VAR: &str = "BOID_CONFIG";
+
+/// Steering weights, new boid parameters and world size read from a TOML file.
+///
+/// Every table and key is optional, missing ones keep their defaults:
+///
+/// ```toml
+/// [steering]
+/// cohesion = 0.4
+/// separate_across_species = true
+///
+/// [spawn]
+/// view_distance = 80.0
+///
+/// [bounds]
+/// min = [0.0, 0.0]
+/// max = [2000.0, 1500.0]
+/// ```
+#[derive(Default)]
+pub struct BoidSettings {
+    pub config: BoidConfig,
+    pub spawn_params: SpawnParams,
+    /// `None` leaves `WorldBounds` to be sized to the window.
+    pub bounds: Option<WorldBounds>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(toml_edit::TomlError),
+    UnknownKey(String),
+    WrongType {
+        key: String,
+        expected: &'static str,
+    },
+    OutOfRange {
+        key: String,
+        requirement: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "could not read {}: {err}", path.display()),
+            Self::Parse(err) => write!(f, "malformed settings file: {err}"),
+            Self::UnknownKey(key) => write!(f, "unknown setting `{key}`"),
+            Self::WrongType { key, expected } => write!(f, "`{key}` has to be {expected}"),
+            Self::OutOfRange { key, requirement } => write!(f, "`{key}` has to be {requirement}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl BoidSettings {
+    /// The first command line argument, or else `BOID_CONFIG`.
+    pub fn path_from_env() -> Option<PathBuf> {
+        std::env::args_os()
+            .nth(1)
+            .or_else(|| std::env::var_os(CONFIG_ENV_VAR))
+            .map(PathBuf::from)
+    }
+
+    /// Reads `path`, a missing file gives the defaults.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(toml) => Self::from_toml(&toml),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                info!("{} not found, using the default settings", path.display());
+                Ok(Self::default())
+            }
+            Err(err) => Err(ConfigError::Io(path.to_owned(), err)),
+        }
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        let document: Document = toml.parse().map_err(ConfigError::Parse)?;
+        let mut settings = Self::default();
+        for (name, item) in document.iter() {
+            let table = item.as_table_like().ok_or_else(|| ConfigError::WrongType {
+                key: name.to_owned(),
+                expected: "a table",
+            })?;
+            let set = match name {
+                "steering" => Self::set_steering,
+                "spawn" => Self::set_spawn,
+                "bounds" => Self::set_bounds,
+                _ => return Err(ConfigError::UnknownKey(name.to_owned())),
+            };
+            for (key, item) in table.iter() {
+                set(&mut settings, &format!("{name}.{key}"), item)?;
+            }
+        }
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn insert_into(self, app: &mut App) {
+        app.insert_resource(self.config)
+            .insert_resource(self.spawn_params);
+        if let Some(bounds) = self.bounds {
+            app.insert_resource(bounds);
+        }
+    }
+
+    fn set_steering(&mut self, key: &str, item: &Item) -> Result<(), ConfigError> {
+        let config = &mut self.config;
+        let flag = match key {
+            "steering.separate_across_species" => Some(&mut config.separate_across_species),
+            "steering.predictive_cohesion" => Some(&mut config.predictive_cohesion),
+            "steering.angular_inertia" => Some(&mut config.angular_inertia),
+            _ => None,
+        };
+        if let Some(flag) = flag {
+            *flag = item.as_bool().ok_or_else(|| ConfigError::WrongType {
+                key: key.to_owned(),
+                expected: "true or false",
+            })?;
+            return Ok(());
+        }
+        let cap = match key {
+            "steering.max_neighbors" => Some(&mut config.max_neighbors),
+            "steering.max_separation_neighbors" => Some(&mut config.max_separation_neighbors),
+            _ => None,
+        };
+        if let Some(cap) = cap {
+            *cap = Some(count(key, item.as_value())?);
+            return Ok(());
+        }
+        if key == "steering.cohesion_weighting" {
+            config.cohesion_weighting = match item.as_str() {
+                Some("uniform") => CohesionWeighting::Uniform,
+                Some("distance") => CohesionWeighting::Distance,
+                Some("density") => CohesionWeighting::Density,
+                _ => {
+                    return Err(ConfigError::WrongType {
+                        key: key.to_owned(),
+                        expected: "\"uniform\", \"distance\" or \"density\"",
+                    })
+                }
+            };
+            return Ok(());
+        }
+        let field = match key {
+            "steering.manual_rotation" => &mut config.manual_rotation,
+            "steering.cohesion" => &mut config.cohesion,
+            "steering.alignment" => &mut config.alignment,
+            "steering.speed_alignment" => &mut config.speed_alignment,
+            "steering.separation" => &mut config.separation,
+            "steering.flee" => &mut config.flee,
+            "steering.obstacle_avoidance" => &mut config.obstacle_avoidance,
+            "steering.follow" => &mut config.follow,
+            "steering.seek" => &mut config.seek,
+            "steering.slowing_radius" => &mut config.slowing_radius,
+            "steering.wander" => &mut config.wander,
+            "steering.mouse" => &mut config.mouse,
+            "steering.flow" => &mut config.flow,
+            "steering.cohesion_smoothing" => &mut config.cohesion_smoothing,
+            "steering.alignment_smoothing" => &mut config.alignment_smoothing,
+            "steering.cohesion_lookahead" => &mut config.cohesion_lookahead,
+            "steering.cohesion_dead_zone" => &mut config.cohesion_dead_zone,
+            "steering.angular_acceleration" => &mut config.angular_acceleration,
+            "steering.angular_damping" => &mut config.angular_damping,
+            "steering.stamina_depletion" => &mut config.stamina_depletion,
+            "steering.stamina_regen" => &mut config.stamina_regen,
+            _ => return Err(ConfigError::UnknownKey(key.to_owned())),
+        };
+        *field = number(key, item.as_value())?;
+        Ok(())
+    }
+
+    fn set_spawn(&mut self, key: &str, item: &Item) -> Result<(), ConfigError> {
+        let params = &mut self.spawn_params;
+        if key == "spawn.speed_range" {
+            let range = vec2(key, item)?;
+            params.speed_range = Some((range.x, range.y));
+            return Ok(());
+        }
+        let field = match key {
+            "spawn.speed" => &mut params.speed,
+            "spawn.rotation_speed" => &mut params.rotation_speed,
+            "spawn.view_distance" => &mut params.view_distance,
+            "spawn.separation_distance" => &mut params.separation_distance,
+            "spawn.radius" => &mut params.radius,
+            "spawn.lifespan" => params.lifespan.insert(0.0),
+            _ => return Err(ConfigError::UnknownKey(key.to_owned())),
+        };
+        *field = number(key, item.as_value())?;
+        Ok(())
+    }
+
+    fn set_bounds(&mut self, key: &str, item: &Item) -> Result<(), ConfigError> {
+        let bounds = self.bounds.get_or_insert_with(WorldBounds::default);
+        let corner = match key {
+            "bounds.min" => &mut bounds.min,
+            "bounds.max" => &mut bounds.max,
+            _ => return Err(ConfigError::UnknownKey(key.to_owned())),
+        };
+        *corner = vec2(key, item)?;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let out_of_range = |key: &str, requirement| {
+            Err(ConfigError::OutOfRange {
+                key: key.to_owned(),
+                requirement,
+            })
+        };
+        let params = &self.spawn_params;
+        for (key, value) in [
+            ("spawn.speed", params.speed),
+            ("spawn.rotation_speed", params.rotation_speed),
+            ("spawn.view_distance", params.view_distance),
+            ("spawn.separation_distance", params.separation_distance),
+        ] {
+            if value < 0.0 {
+                return out_of_range(key, "zero or more");
+            }
+        }
+        if params.radius <= 0.0 {
+            return out_of_range("spawn.radius", "more than zero");
+        }
+        if let Some((min, max)) = params.speed_range {
+            if min < 0.0 {
+                return out_of_range("spawn.speed_range", "zero or more");
+            }
+            if max < min {
+                return out_of_range("spawn.speed_range", "a [min, max] array with min <= max");
+            }
+        }
+        if params.lifespan.is_some_and(|lifespan| lifespan <= 0.0) {
+            return out_of_range("spawn.lifespan", "more than zero");
+        }
+        if !(0.0..=1.0).contains(&self.config.cohesion_smoothing) {
+            return out_of_range("steering.cohesion_smoothing", "between 0 and 1");
+        }
+        if !(0.0..=1.0).contains(&self.config.alignment_smoothing) {
+            return out_of_range("steering.alignment_smoothing", "between 0 and 1");
+        }
+        for (key, cap) in [
+            ("steering.max_neighbors", self.config.max_neighbors),
+            (
+                "steering.max_separation_neighbors",
+                self.config.max_separation_neighbors,
+            ),
+        ] {
+            if cap == Some(0) {
+                return out_of_range(key, "more than zero");
+            }
+        }
+        if self.config.cohesion_lookahead < 0.0 {
+            return out_of_range("steering.cohesion_lookahead", "zero or more");
+        }
+        for (key, value) in [
+            (
+                "steering.cohesion_dead_zone",
+                self.config.cohesion_dead_zone,
+            ),
+            (
+                "steering.angular_acceleration",
+                self.config.angular_acceleration,
+            ),
+            ("steering.angular_damping", self.config.angular_damping),
+            ("steering.slowing_radius", self.config.slowing_radius),
+            ("steering.stamina_depletion", self.config.stamina_depletion),
+            ("steering.stamina_regen", self.config.stamina_regen),
+        ] {
+            if value < 0.0 {
+                return out_of_range(key, "zero or more");
+            }
+        }
+        if let Some(bounds) = self.bounds {
+            if bounds.max.cmple(bounds.min).any() {
+                return out_of_range("bounds.max", "above and to the right of bounds.min");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A two number array as `Vec2`.
+fn vec2(key: &str, item: &Item) -> Result<Vec2, ConfigError> {
+    let wrong_type = || ConfigError::WrongType {
+        key: key.to_owned(),
+        expected: "an [x, y] array",
+    };
+    let array = item.as_array().ok_or_else(wrong_type)?;
+    let [x, y] = array.iter().collect::<Vec<_>>()[..] else {
+        return Err(wrong_type());
+    };
+    Ok(Vec2::new(number(key, Some(x))?, number(key, Some(y))?))
+}
+
+/// A float or integer value as `f32`.
+fn number(key: &str, value: Option<&Value>) -> Result<f32, ConfigError> {
+    value
+        .and_then(|value| {
+            value
+                .as_float()
+                .or_else(|| value.as_integer().map(|value| value as f64))
+        })
+        .map(|value| value as f32)
+        .ok_or_else(|| ConfigError::WrongType {
+            key: key.to_owned(),
+            expected: "a number",
+        })
+}
+
+/// A non-negative integer value as `usize`.
+fn count(key: &str, value: Option<&Value>) -> Result<usize, ConfigError> {
+    let value = value
+        .and_then(Value::as_integer)
+        .ok_or_else(|| ConfigError::WrongType {
+            key: key.to_owned(),
+            expected: "a whole number",
+        })?;
+    usize::try_from(value).map_err(|_| ConfigError::OutOfRange {
+        key: key.to_owned(),
+        requirement: "more than zero",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_override_only_the_given_keys() {
+        let settings = BoidSettings::from_toml(
+            "[steering]\ncohesion = 1\nseparate_across_species = true\n\
+             cohesion_weighting = \"density\"\nmax_neighbors = 12\nmax_separation_neighbors = 7\n\n\
+             [spawn]\nview_distance = 80.5\n\n\
+             [bounds]\nmax = [2000, 1500.0]\n",
+        )
+        .unwrap();
+        assert_eq!(settings.config.cohesion, 1.0);
+        assert!(settings.config.separate_across_species);
+        assert_eq!(
+            settings.config.cohesion_weighting,
+            CohesionWeighting::Density
+        );
+        assert_eq!(settings.config.max_neighbors, Some(12));
+        assert_eq!(settings.config.max_separation_neighbors, Some(7));
+        assert_eq!(settings.config.alignment, BoidConfig::default().alignment);
+        assert_eq!(settings.spawn_params.view_distance, 80.5);
+        assert_eq!(settings.spawn_params.speed, SpawnParams::default().speed);
+        let bounds = settings.bounds.unwrap();
+        assert_eq!(bounds.min, Vec2::ZERO);
+        assert_eq!(bounds.max, Vec2::new(2000.0, 1500.0));
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        let error = |toml| BoidSettings::from_toml(toml).err().unwrap().to_string();
+        assert_eq!(
+            error("[spawn]\nview_distance = -1.0"),
+            "`spawn.view_distance` has to be zero or more"
+        );
+        assert_eq!(
+            error("[steering]\ncohesoin = 1.0"),
+            "unknown setting `steering.cohesoin`"
+        );
+        assert_eq!(
+            error("[bounds]\nmin = [1.0]"),
+            "`bounds.min` has to be an [x, y] array"
+        );
+        assert_eq!(
+            error("[spawn]\nspeed_range = [30, 10]"),
+            "`spawn.speed_range` has to be a [min, max] array with min <= max"
+        );
+        assert_eq!(
+            error("[spawn]\nspeed = \"fast\""),
+            "`spawn.speed` has to be a number"
+        );
+        assert_eq!(
+            error("[steering]\ncohesion_weighting = \"closest\""),
+            "`steering.cohesion_weighting` has to be \"uniform\", \"distance\" or \"density\""
+        );
+        assert_eq!(
+            error("[steering]\nmax_neighbors = 0"),
+            "`steering.max_neighbors` has to be more than zero"
+        );
+        assert_eq!(
+            error("[steering]\nmax_separation_neighbors = 0"),
+            "`steering.max_separation_neighbors` has to be more than zero"
+        );
+        assert_eq!(
+            error("[steering]\nmax_separation_neighbors = 2.5"),
+            "`steering.max_separation_neighbors` has to be a whole number"
+        );
+    }
+
+    #[test]
+    fn sample_settings_file_is_valid() {
+        BoidSettings::from_toml(include_str!("../examples/boids.toml")).unwrap();
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let settings = BoidSettings::load("does/not/exist.toml").unwrap();
+        assert!(settings.bounds.is_none());
+        assert_eq!(settings.config.cohesion, BoidConfig::default().cohesion);
+    }
+}
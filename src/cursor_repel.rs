@@ -0,0 +1,61 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{steer_boid, Boid, MouseInfluence, SpatialQuery};
+
+/// A hard pushback from the cursor that parts the flock as it sweeps through, off by default.
+/// R toggles it.
+///
+/// Unlike `MouseMode::Repel` every boid inside `radius` is pushed just as hard, so the cursor
+/// clears a sharp hole instead of nudging the flock aside.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct CursorRepel {
+    pub enabled: bool,
+    pub radius: f32,
+    /// Steering weight of the pushback, like the weights in `BoidConfig`.
+    pub strength: f32,
+}
+
+impl Default for CursorRepel {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 60.0,
+            strength: 10.0,
+        }
+    }
+}
+
+pub fn toggle_cursor_repel_system(mut repel: ResMut<CursorRepel>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::R) {
+        repel.enabled = !repel.enabled;
+    }
+}
+
+/// Turns the boids within `CursorRepel::radius` of the cursor straight away from it.
+///
+/// Reads the cursor from `MouseInfluence::position`, so nothing happens while it is outside the
+/// window or `track_mouse_influence_system` isn't running.
+pub fn cursor_repel_system(
+    repel: Res<CursorRepel>,
+    influence: Res<MouseInfluence>,
+    spatial: SpatialQuery,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+) {
+    if !repel.enabled || repel.radius <= 0.0 {
+        return;
+    }
+    let Some(cursor) = influence.position else {
+        return;
+    };
+
+    for (_, entity) in spatial.within_distance(cursor, repel.radius) {
+        let Some(entity) = entity else { continue };
+        let Ok((transform, mut boid)) = boid_query.get_mut(entity) else {
+            continue;
+        };
+        let away = transform.translation.xy() - cursor;
+        let weight = boid.rotation_speed * repel.strength;
+        steer_boid(&mut boid, away, weight);
+    }
+}
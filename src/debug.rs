@@ -0,0 +1,275 @@
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+#[cfg(not(feature = "gizmos"))]
+use bevy_prototype_debug_lines::{DebugLines, DebugLinesPlugin};
+use std::marker::PhantomData;
+
+use crate::{draw_x, flocking, pair_separation_distance, Boid, NeighborCache, Selected};
+
+const DIRECTION_LENGTH: f32 = 20.0;
+const SEPARATION_COLOR: Color = Color::RED;
+const COHESION_COLOR: Color = Color::GREEN;
+const LINK_COLOR: Color = Color::rgba(1.0, 1.0, 0.0, 0.4);
+const LINE_LIFETIME: f32 = 1.0;
+/// Segments `DebugDraw::circle` approximates a circle with.
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[cfg(not(any(feature = "debug_lines", feature = "gizmos")))]
+compile_error!("enable either the `debug_lines` or the `gizmos` feature for the debug drawing");
+
+/// Which steering vectors `debug_visualization_system` draws, all off by default, and whose
+/// neighbor links `neighbor_links_system` draws.
+#[derive(Resource)]
+pub struct DebugVisualization {
+    /// Each boid's heading.
+    pub directions: bool,
+    /// The vector to the smoothed cohesion target, with the target itself marked by an x.
+    pub cohesion_target: bool,
+    /// The direction away from the boids close enough to separate from, the closest weighing most.
+    pub separation: bool,
+    /// The `FlowField` as a grid of arrows, see `draw_flow_field_system`.
+    pub flow_field: bool,
+    /// Whose lines to their neighbors `neighbor_links_system` draws.
+    pub neighbor_links: NeighborLinks,
+    /// How many frames every debug line lasts, in multiples of the last frame's length, see
+    /// `LineDuration`. Gizmos always last one frame, so the `gizmos` feature ignores it.
+    pub line_lifetime: f32,
+}
+
+impl Default for DebugVisualization {
+    fn default() -> Self {
+        Self {
+            directions: false,
+            cohesion_target: false,
+            separation: false,
+            flow_field: false,
+            neighbor_links: NeighborLinks::default(),
+            line_lifetime: LINE_LIFETIME,
+        }
+    }
+}
+
+/// Adds what `DebugDraw` draws with: `DebugLinesPlugin`, or nothing with the `gizmos` feature
+/// since `GizmoPlugin` is part of `DefaultPlugins`.
+pub struct DebugDrawPlugin;
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, _app: &mut App) {
+        #[cfg(not(feature = "gizmos"))]
+        _app.add_plugins(DebugLinesPlugin::default());
+    }
+}
+
+/// The lines every debug overlay draws with, bevy's `Gizmos` with the `gizmos` feature and
+/// `DebugLines` otherwise.
+///
+/// Both show a line for the frame it is drawn in, so the overlays redraw theirs each frame.
+#[derive(SystemParam)]
+pub struct DebugDraw<'w, 's> {
+    #[cfg(feature = "gizmos")]
+    gizmos: Gizmos<'s>,
+    #[cfg(not(feature = "gizmos"))]
+    lines: ResMut<'w, DebugLines>,
+    #[cfg(not(feature = "gizmos"))]
+    duration: LineDuration<'w>,
+    marker: PhantomData<(&'w (), &'s ())>,
+}
+
+impl DebugDraw<'_, '_> {
+    pub fn line(&mut self, start: Vec3, end: Vec3) {
+        self.line_colored(start, end, Color::WHITE);
+    }
+
+    pub fn line_colored(&mut self, start: Vec3, end: Vec3, color: Color) {
+        self.line_gradient(start, end, color, color);
+    }
+
+    pub fn line_gradient(&mut self, start: Vec3, end: Vec3, start_color: Color, end_color: Color) {
+        #[cfg(feature = "gizmos")]
+        self.gizmos
+            .line_gradient(start, end, start_color, end_color);
+        #[cfg(not(feature = "gizmos"))]
+        self.lines
+            .line_gradient(start, end, self.duration.get(), start_color, end_color);
+    }
+
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        #[cfg(feature = "gizmos")]
+        self.gizmos
+            .circle_2d(center, radius, color)
+            .segments(CIRCLE_SEGMENTS);
+        #[cfg(not(feature = "gizmos"))]
+        {
+            let point = |i: usize| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                (center + Vec2::from_angle(angle) * radius).extend(0.0)
+            };
+            for i in 0..CIRCLE_SEGMENTS {
+                self.line_colored(point(i), point(i + 1), color);
+            }
+        }
+    }
+}
+
+/// The duration `DebugDraw` passes to `DebugLines`, so lines last as long as a frame does.
+///
+/// Every line shows for at least the frame it is drawn in, and those drawing each frame redraw
+/// it for the next. A fixed duration lingers for several frames at a high framerate, the
+/// default `DebugVisualization::line_lifetime` of one frame replaces each line exactly when it
+/// is refreshed; above 1 the old and new line overlap for a frame.
+#[cfg(not(feature = "gizmos"))]
+#[derive(SystemParam)]
+pub struct LineDuration<'w> {
+    time: Res<'w, Time>,
+    debug: Option<Res<'w, DebugVisualization>>,
+}
+
+#[cfg(not(feature = "gizmos"))]
+impl LineDuration<'_> {
+    pub fn get(&self) -> f32 {
+        let lifetime = self
+            .debug
+            .as_ref()
+            .map_or(LINE_LIFETIME, |debug| debug.line_lifetime);
+        self.time.delta_seconds() * lifetime.max(0.0)
+    }
+}
+
+/// Which boids get a line to every neighbor within their `view_distance`.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NeighborLinks {
+    Off,
+    /// Only the boids marked `Selected`.
+    #[default]
+    Selected,
+    /// Every boid, which is one line per pair of neighbors and gets slow in large flocks.
+    All,
+}
+
+impl NeighborLinks {
+    fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Selected,
+            Self::Selected => Self::All,
+            Self::All => Self::Off,
+        }
+    }
+}
+
+/// F2, F3 and F4 toggle the direction, cohesion and separation vectors, J the flow field arrows.
+/// N cycles the neighbor links between the selected boid, every boid and none.
+pub fn toggle_debug_visualization_system(
+    mut debug: ResMut<DebugVisualization>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::F2) {
+        debug.directions = !debug.directions;
+    }
+    if keys.just_pressed(KeyCode::F3) {
+        debug.cohesion_target = !debug.cohesion_target;
+    }
+    if keys.just_pressed(KeyCode::F4) {
+        debug.separation = !debug.separation;
+    }
+    if keys.just_pressed(KeyCode::J) {
+        debug.flow_field = !debug.flow_field;
+    }
+    if keys.just_pressed(KeyCode::N) {
+        debug.neighbor_links = debug.neighbor_links.next();
+    }
+}
+
+/// Draws a faint line from boids to each of their neighbors within `view_distance`, the same
+/// neighbors the flocking systems see in the `NeighborCache`.
+///
+/// With `NeighborLinks::All` every pair is drawn from both ends, so the cost grows with the number
+/// of boids times their neighbors.
+pub fn neighbor_links_system(
+    debug: Res<DebugVisualization>,
+    neighbor_cache: Res<NeighborCache>,
+    boid_query: Query<(&Transform, &Boid, Entity, Option<&Selected>)>,
+    mut lines: DebugDraw,
+) {
+    if debug.neighbor_links == NeighborLinks::Off {
+        return;
+    }
+    for (transform, boid, entity, selected) in boid_query.iter() {
+        if debug.neighbor_links == NeighborLinks::Selected && selected.is_none() {
+            continue;
+        }
+        let position = transform.translation.xy();
+        for (neighbor, _) in neighbor_cache
+            .get(entity)
+            .iter()
+            .filter(|(_, other)| *other != Some(entity))
+            .filter(|(neighbor, _)| neighbor.distance(position) <= boid.view_distance)
+        {
+            lines.line_colored(position.extend(0.0), neighbor.extend(0.0), LINK_COLOR);
+        }
+    }
+}
+
+/// Draws the enabled steering vectors, separation from the same neighbor lists the flocking
+/// systems use.
+pub fn debug_visualization_system(
+    debug: Res<DebugVisualization>,
+    neighbor_cache: Res<NeighborCache>,
+    boid_query: Query<(&Transform, &Boid, Entity)>,
+    mut lines: DebugDraw,
+) {
+    if !debug.directions && !debug.cohesion_target && !debug.separation {
+        return;
+    }
+
+    let radius_map: HashMap<Entity, f32> = boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, boid.radius))
+        .collect();
+
+    for (transform, boid, entity) in boid_query.iter() {
+        let position = transform.translation.xy();
+        let neighbors = neighbor_cache.get(entity);
+
+        if debug.directions {
+            let heading = boid.velocity.try_normalize().unwrap_or(boid.direction);
+            lines.line(
+                position.extend(0.0),
+                (position + heading * DIRECTION_LENGTH).extend(0.0),
+            );
+        }
+
+        if debug.cohesion_target && boid.cohesion_target != Vec2::ZERO {
+            lines.line_colored(
+                position.extend(0.0),
+                boid.cohesion_target.extend(0.0),
+                COHESION_COLOR,
+            );
+            draw_x(&mut lines, boid.cohesion_target);
+        }
+
+        if debug.separation {
+            let crowding: Vec<Vec2> = neighbors
+                .iter()
+                .filter(|(_, option)| *option != Some(entity))
+                .filter(|(pos, option)| {
+                    let other_radius = option
+                        .and_then(|other| radius_map.get(&other).copied())
+                        .unwrap_or(boid.radius);
+                    pos.distance(position) <= pair_separation_distance(boid, other_radius)
+                })
+                .map(|(pos, _)| *pos)
+                .collect();
+            if let Some(away) = flocking::separation(position, &crowding) {
+                // as long as the distance it keeps from boids its own size
+                let away = away * pair_separation_distance(boid, boid.radius);
+                lines.line_colored(
+                    position.extend(0.0),
+                    (position + away).extend(0.0),
+                    SEPARATION_COLOR,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{Boid, DebugDraw, SpatialBackend, WorldBounds};
+
+/// Cell size used while the KD-tree backend is active, roughly one default view distance.
+const DEFAULT_CELL_SIZE: f32 = 50.0;
+/// Seconds between rebinning the flock, drawing happens every frame from the last counts.
+const DENSITY_UPDATE_INTERVAL: f32 = 0.5;
+const SPARSE_COLOR: Color = Color::rgb(0.1, 0.3, 1.0);
+const DENSE_COLOR: Color = Color::rgb(1.0, 0.2, 0.1);
+
+/// Outlines each cell of `WorldBounds` holding boids, from blue for the emptiest to red for the
+/// most crowded. H toggles it.
+#[derive(Resource)]
+pub struct DensityOverlay {
+    pub visible: bool,
+    /// `None` uses the cell size of `SpatialBackend::Grid`, or a default with the KD-tree.
+    pub cell_size: Option<f32>,
+    timer: Timer,
+    bins: DensityBins,
+}
+
+impl Default for DensityOverlay {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            cell_size: None,
+            timer: Timer::from_seconds(DENSITY_UPDATE_INTERVAL, TimerMode::Repeating),
+            bins: DensityBins::default(),
+        }
+    }
+}
+
+/// Boid counts per square cell, counted from `WorldBounds::min`.
+#[derive(Default, Debug, PartialEq)]
+struct DensityBins {
+    cell_size: f32,
+    counts: HashMap<IVec2, usize>,
+}
+
+impl DensityBins {
+    /// Positions outside `bounds` are left out.
+    fn new(positions: impl Iterator<Item = Vec2>, bounds: &WorldBounds, cell_size: f32) -> Self {
+        let mut counts = HashMap::default();
+        for position in positions {
+            if position.cmplt(bounds.min).any() || position.cmpgt(bounds.max).any() {
+                continue;
+            }
+            let cell = ((position - bounds.min) / cell_size).floor().as_ivec2();
+            *counts.entry(cell).or_default() += 1;
+        }
+        Self { cell_size, counts }
+    }
+}
+
+pub fn toggle_density_overlay_system(
+    mut overlay: ResMut<DensityOverlay>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::H) {
+        overlay.visible = !overlay.visible;
+        // rebin on the next update instead of showing counts from when it was last hidden
+        let duration = overlay.timer.duration();
+        overlay.timer.set_elapsed(duration);
+    }
+}
+
+pub fn density_overlay_system(
+    mut overlay: ResMut<DensityOverlay>,
+    time: Res<Time>,
+    backend: Res<SpatialBackend>,
+    bounds: Res<WorldBounds>,
+    boid_query: Query<&Transform, With<Boid>>,
+    mut lines: DebugDraw,
+) {
+    if !overlay.visible {
+        return;
+    }
+    if overlay.timer.tick(time.delta()).just_finished() {
+        let cell_size = overlay.cell_size.unwrap_or(match *backend {
+            SpatialBackend::Grid { cell_size } => cell_size,
+            SpatialBackend::KdTree => DEFAULT_CELL_SIZE,
+        });
+        let positions = boid_query
+            .iter()
+            .map(|transform| transform.translation.xy());
+        overlay.bins = DensityBins::new(positions, &bounds, cell_size);
+    }
+
+    let bins = &overlay.bins;
+    let Some(&most) = bins.counts.values().max() else {
+        return;
+    };
+    let sparse = Vec4::from_array(SPARSE_COLOR.as_rgba_f32());
+    let dense = Vec4::from_array(DENSE_COLOR.as_rgba_f32());
+    for (cell, count) in bins.counts.iter() {
+        let [r, g, b, a] = sparse.lerp(dense, *count as f32 / most as f32).to_array();
+        let color = Color::rgba(r, g, b, a);
+        let min = bounds.min + cell.as_vec2() * bins.cell_size;
+        let max = min + Vec2::splat(bins.cell_size);
+        let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+        for (start, end) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+            lines.line_colored(start.extend(0.0), end.extend(0.0), color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boids_are_binned_by_cell_from_the_bounds_corner() {
+        let bounds = WorldBounds {
+            min: Vec2::new(-100.0, -100.0),
+            max: Vec2::new(100.0, 100.0),
+        };
+        let positions = [
+            Vec2::new(-95.0, -95.0),
+            Vec2::new(-60.0, -99.0),
+            Vec2::new(-40.0, -95.0),
+            Vec2::new(20.0, 30.0),
+            // outside the world, not counted
+            Vec2::new(150.0, 0.0),
+        ];
+        let bins = DensityBins::new(positions.into_iter(), &bounds, 50.0);
+        assert_eq!(bins.counts.len(), 3);
+        assert_eq!(bins.counts[&IVec2::new(0, 0)], 2);
+        assert_eq!(bins.counts[&IVec2::new(1, 0)], 1);
+        assert_eq!(bins.counts.get(&IVec2::new(2, 2)), Some(&1));
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics, DiagnosticsStore};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{Boid, NeighborCache, SpatialQuery};
+
+/// How many boids are sampled for the neighbor count when no `NeighborCache` is available.
+const NEIGHBOR_SAMPLE_SIZE: usize = 100;
+
+/// Adds average boid speed, average neighbor count and boid count diagnostics.
+///
+/// Add `LogDiagnosticsPlugin` as well to print them to the console.
+#[derive(Default)]
+pub struct BoidDiagnosticsPlugin;
+
+impl Plugin for BoidDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, Self::setup_system)
+            .add_systems(Update, boid_diagnostics_system);
+    }
+}
+
+impl BoidDiagnosticsPlugin {
+    pub const AVERAGE_SPEED: DiagnosticId =
+        DiagnosticId::from_u128(51746759568428922846273869977407535661);
+    pub const AVERAGE_NEIGHBORS: DiagnosticId =
+        DiagnosticId::from_u128(291090936629102143324146314533398538850);
+    pub const BOID_COUNT: DiagnosticId =
+        DiagnosticId::from_u128(236318181316348065209584995918198188022);
+
+    pub fn setup_system(mut diagnostics: ResMut<DiagnosticsStore>) {
+        diagnostics.add(Diagnostic::new(
+            Self::AVERAGE_SPEED,
+            "boid_average_speed",
+            20,
+        ));
+        diagnostics.add(Diagnostic::new(
+            Self::AVERAGE_NEIGHBORS,
+            "boid_average_neighbors",
+            20,
+        ));
+        diagnostics
+            .add(Diagnostic::new(Self::BOID_COUNT, "boid_count", 1).with_smoothing_factor(0.0));
+    }
+}
+
+pub fn boid_diagnostics_system(
+    mut diagnostics: Diagnostics,
+    boid_query: Query<(&Transform, &Boid, Entity)>,
+    neighbor_cache: Option<Res<NeighborCache>>,
+    spatial: SpatialQuery,
+) {
+    let boid_count = boid_query.iter().len();
+    diagnostics.add_measurement(BoidDiagnosticsPlugin::BOID_COUNT, || boid_count as f64);
+    if boid_count == 0 {
+        return;
+    }
+
+    let summed_speed: f32 = boid_query
+        .iter()
+        .map(|(_, boid, _)| boid.velocity.length())
+        .sum();
+    diagnostics.add_measurement(BoidDiagnosticsPlugin::AVERAGE_SPEED, || {
+        (summed_speed / boid_count as f32) as f64
+    });
+
+    // the cache already holds every boid's neighbors, without it only a sample is queried
+    let (neighbor_total, sampled) = match neighbor_cache {
+        Some(neighbor_cache) => boid_query
+            .iter()
+            .map(|(transform, boid, entity)| {
+                let position = transform.translation.xy();
+                neighbor_cache
+                    .get(entity)
+                    .iter()
+                    .filter(|(pos, neighbor)| {
+                        *neighbor != Some(entity) && pos.distance(position) <= boid.view_distance
+                    })
+                    .count()
+            })
+            .fold((0, 0), |(total, sampled), count| {
+                (total + count, sampled + 1)
+            }),
+        None => boid_query
+            .iter()
+            .take(NEIGHBOR_SAMPLE_SIZE)
+            .map(|(transform, boid, entity)| {
+                spatial
+                    .within_distance(transform.translation.xy(), boid.view_distance)
+                    .iter()
+                    .filter(|(_, neighbor)| *neighbor != Some(entity))
+                    .count()
+            })
+            .fold((0, 0), |(total, sampled), count| {
+                (total + count, sampled + 1)
+            }),
+    };
+    diagnostics.add_measurement(BoidDiagnosticsPlugin::AVERAGE_NEIGHBORS, || {
+        neighbor_total as f64 / sampled as f64
+    });
+}
@@ -0,0 +1,255 @@
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_spatial::kdtree::KDTree3;
+use bevy_spatial::point::Point3;
+use bevy_spatial::SpatialAccess;
+use kd_tree::KdTree;
+use rand::Rng;
+
+use crate::{BoidConfig, RngResource};
+
+type NNTree3d = KDTree3<Boid3d>;
+
+/// A boid flying in 3D, the flocking math is the same as for [`crate::Boid`] with `Vec3`.
+#[derive(Component)]
+pub struct Boid3d {
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub direction: Vec3,
+    pub view_distance: f32,
+    pub separation_distance: f32,
+}
+
+impl Default for Boid3d {
+    fn default() -> Self {
+        Self {
+            speed: 20.0,
+            rotation_speed: 3.0,
+            direction: Vec3::Y,
+            view_distance: 50.0,
+            separation_distance: 10.0,
+        }
+    }
+}
+
+/// Boids leaving this box centered on the origin wrap around to the opposite face.
+#[derive(Resource, Clone, Copy)]
+pub struct Bounds3d {
+    pub half_extent: Vec3,
+}
+
+impl Default for Bounds3d {
+    fn default() -> Self {
+        Self {
+            half_extent: Vec3::splat(200.0),
+        }
+    }
+}
+
+/// Registers the 3D flock: KD-tree, steering and movement in `FixedUpdate`, mesh orientation.
+///
+/// Steering weights are read from the same [`BoidConfig`] as the 2D simulation.
+pub struct Boid3dPlugin;
+
+impl Plugin for Boid3dPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoidConfig>()
+            .init_resource::<Bounds3d>()
+            .init_resource::<RngResource>()
+            .init_resource::<NNTree3d>()
+            .add_systems(PreUpdate, rebuild_tree3d_system)
+            .add_systems(
+                FixedUpdate,
+                (
+                    boid3d_flocking_system,
+                    move_boid3d_system.after(boid3d_flocking_system),
+                ),
+            )
+            .add_systems(Update, orient_boid3d_system);
+    }
+}
+
+/// A uniformly distributed unit vector.
+pub fn random_direction3d(rng: &mut impl Rng) -> Vec3 {
+    let z: f32 = rng.gen_range(-1.0..=1.0);
+    let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+    let radius = (1.0 - z * z).sqrt();
+    Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+}
+
+/// Rebuilds the 3D tree every frame in `PreUpdate`, before the fixed steps read it.
+pub fn rebuild_tree3d_system(
+    mut tree: ResMut<NNTree3d>,
+    boid_query: Query<(Entity, &Transform), With<Boid3d>>,
+) {
+    let points: Vec<Point3> = boid_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation).into())
+        .collect();
+    tree.tree = KdTree::build_by_ordered_float(points);
+}
+
+/// Cohesion, alignment and separation in one pass over the tree.
+pub fn boid3d_flocking_system(
+    tree: Res<NNTree3d>,
+    mut boid_query: Query<(&Transform, &mut Boid3d, Entity)>,
+    time: Res<FixedTime>,
+    config: Res<BoidConfig>,
+) {
+    let direction_map: HashMap<Entity, Vec3> = boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, boid.direction))
+        .collect();
+    let delta_seconds = time.period.as_secs_f32();
+
+    boid_query
+        .par_iter_mut()
+        .for_each_mut(|(transform, mut boid, entity)| {
+            let position = transform.translation;
+            let radius = boid.view_distance.max(boid.separation_distance);
+
+            let mut centroid = Vec3::ZERO;
+            let mut heading = Vec3::ZERO;
+            let mut visible = 0.0;
+            let mut away = Vec3::ZERO;
+            let mut crowding = 0.0;
+            for (pos, other) in tree.within_distance(position, radius) {
+                let Some(other) = other.filter(|other| *other != entity) else {
+                    continue;
+                };
+                let distance = pos.distance(position);
+                if distance <= boid.view_distance {
+                    centroid += pos;
+                    heading += direction_map.get(&other).copied().unwrap_or(Vec3::ZERO);
+                    visible += 1.0;
+                }
+                if distance <= boid.separation_distance {
+                    away += position - pos;
+                    crowding += 1.0;
+                }
+            }
+
+            let strength = boid.rotation_speed * delta_seconds;
+            if visible > 0.0 {
+                let cohesion = centroid / visible - position;
+                rotate_boid3d_direction(&mut boid, cohesion, strength * config.cohesion);
+                rotate_boid3d_direction(&mut boid, heading / visible, strength * config.alignment);
+            }
+            if crowding > 0.0 {
+                rotate_boid3d_direction(&mut boid, away / crowding, strength * config.separation);
+            }
+        });
+}
+
+pub fn move_boid3d_system(
+    mut boid_query: Query<(&mut Transform, &Boid3d)>,
+    time: Res<FixedTime>,
+    bounds: Res<Bounds3d>,
+) {
+    for (mut transform, boid) in boid_query.iter_mut() {
+        transform.translation += boid.direction * boid.speed * time.period.as_secs_f32();
+        transform.translation = wrap_position3d(transform.translation, bounds.half_extent);
+    }
+}
+
+/// Points each boid's local +Y along its direction.
+pub fn orient_boid3d_system(mut boid_query: Query<(&mut Transform, &Boid3d)>) {
+    for (mut transform, boid) in boid_query.iter_mut() {
+        transform.rotation = Quat::from_rotation_arc(Vec3::Y, boid.direction);
+    }
+}
+
+/// A free-fly camera: WASD to move, Space/Shift for up/down, hold the right mouse button to look.
+#[derive(Component)]
+pub struct FlyCamera {
+    pub speed: f32,
+    pub sensitivity: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self {
+            speed: 150.0,
+            sensitivity: 0.003,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+}
+
+pub fn fly_camera_system(
+    mut camera_query: Query<(&mut Transform, &mut FlyCamera)>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    time: Res<Time>,
+) {
+    let look: Vec2 = mouse_motion.iter().map(|motion| motion.delta).sum();
+    for (mut transform, mut camera) in camera_query.iter_mut() {
+        if buttons.pressed(MouseButton::Right) {
+            camera.yaw -= look.x * camera.sensitivity;
+            camera.pitch = (camera.pitch - look.y * camera.sensitivity).clamp(-1.54, 1.54);
+            transform.rotation = Quat::from_euler(EulerRot::YXZ, camera.yaw, camera.pitch, 0.0);
+        }
+
+        let mut movement = Vec3::ZERO;
+        for (key, direction) in [
+            (KeyCode::W, transform.forward()),
+            (KeyCode::S, transform.back()),
+            (KeyCode::A, transform.left()),
+            (KeyCode::D, transform.right()),
+            (KeyCode::Space, Vec3::Y),
+            (KeyCode::ShiftLeft, Vec3::NEG_Y),
+        ] {
+            if keys.pressed(key) {
+                movement += direction;
+            }
+        }
+        transform.translation += movement.normalize_or_zero() * camera.speed * time.delta_seconds();
+    }
+}
+
+fn rotate_boid3d_direction(boid: &mut Boid3d, target_vector: Vec3, strength: f32) {
+    let target_vector = target_vector.normalize_or_zero();
+    if target_vector == Vec3::ZERO {
+        return;
+    }
+    // a target exactly opposite the heading would lerp through zero
+    boid.direction = boid
+        .direction
+        .lerp(target_vector, strength)
+        .try_normalize()
+        .unwrap_or(boid.direction);
+}
+
+fn wrap_position3d(position: Vec3, half_extent: Vec3) -> Vec3 {
+    let size = half_extent * 2.0;
+    let shifted = position + half_extent;
+    let wrapped = shifted - size * (shifted / size).floor();
+    wrapped - half_extent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_boid3d_direction_ignores_zero_target() {
+        let mut boid = Boid3d {
+            direction: Vec3::X,
+            ..default()
+        };
+        rotate_boid3d_direction(&mut boid, Vec3::ZERO, 0.5);
+        assert_eq!(boid.direction, Vec3::X);
+    }
+
+    #[test]
+    fn wrap_position3d_moves_to_opposite_face() {
+        let half_extent = Vec3::splat(100.0);
+        let wrapped = wrap_position3d(Vec3::new(105.0, 0.0, -101.0), half_extent);
+        assert!(wrapped.abs_diff_eq(Vec3::new(-95.0, 0.0, 99.0), 1e-4));
+    }
+}
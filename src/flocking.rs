@@ -0,0 +1,502 @@
+//! The flocking rules as plain functions of neighbor positions, headings and speeds.
+//!
+//! Nothing here touches the ECS. The systems gather the neighbors a boid can see and call in, so
+//! the math can be tested without building an `App`.
+
+use bevy::math::Vec2;
+
+/// Closest a neighbor counts as in [`separation`], so one almost on top doesn't weigh infinitely.
+const MIN_SEPARATION_DISTANCE: f32 = 0.1;
+/// Closest a neighbor counts as in [`CohesionWeighting::Distance`], for the same reason.
+const MIN_COHESION_DISTANCE: f32 = 1.0;
+
+/// How strongly each rule contributes to [`steering`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SteeringWeights {
+    pub cohesion: f32,
+    pub alignment: f32,
+    pub separation: f32,
+}
+
+impl SteeringWeights {
+    /// Boids keep their distance and barely stick together.
+    pub const SEPARATED: Self = Self {
+        cohesion: 0.0,
+        alignment: 0.1,
+        separation: 0.6,
+    };
+    /// A tight flock that leaves only a little room between boids.
+    pub const COHESIVE: Self = Self {
+        cohesion: 0.4,
+        alignment: 0.3,
+        separation: 0.1,
+    };
+
+    /// Weights blended from [`Self::SEPARATED`] at 0 to [`Self::COHESIVE`] at 1, clamped to that
+    /// range. Halfway gives the default `BoidConfig` weights.
+    pub fn from_cohesiveness(cohesiveness: f32) -> Self {
+        let t = cohesiveness.clamp(0.0, 1.0);
+        // exact at both ends, unlike from + (to - from) * t
+        let lerp = |from: f32, to: f32| from * (1.0 - t) + to * t;
+        let (from, to) = (Self::SEPARATED, Self::COHESIVE);
+        Self {
+            cohesion: lerp(from.cohesion, to.cohesion),
+            alignment: lerp(from.alignment, to.alignment),
+            separation: lerp(from.separation, to.separation),
+        }
+    }
+}
+
+/// How much each neighbor counts toward the point cohesion steers to, see [`weighted_centroid`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CohesionWeighting {
+    /// Every neighbor counts the same, so boids pile into the densest part of what they see.
+    #[default]
+    Uniform,
+    /// One over the neighbor's distance, so a nearby small group draws the boid more than a
+    /// dense core further off.
+    Distance,
+    /// One over the number of neighbors crowded around it, so a dense core pulls about as hard as
+    /// a single isolated group.
+    Density,
+}
+
+/// Average of `positions`, `None` if there are none.
+pub fn centroid(positions: &[Vec2]) -> Option<Vec2> {
+    if positions.is_empty() {
+        return None;
+    }
+    Some(positions.iter().sum::<Vec2>() / positions.len() as f32)
+}
+
+/// Where `positions` will be after moving by their `velocities` for `lookahead` seconds.
+///
+/// Steering toward their centroid leads a moving flock instead of chasing where it was.
+pub fn predicted_positions(positions: &[Vec2], velocities: &[Vec2], lookahead: f32) -> Vec<Vec2> {
+    positions
+        .iter()
+        .zip(velocities)
+        .map(|(position, velocity)| *position + *velocity * lookahead)
+        .collect()
+}
+
+/// Average of `positions` weighted as `weighting` says, as seen from a boid at `position`,
+/// `None` if there are none.
+///
+/// With [`CohesionWeighting::Density`] each position counts one over the number of positions,
+/// itself included, within `crowd_radius` of it. [`CohesionWeighting::Uniform`] is the plain
+/// [`centroid`].
+pub fn weighted_centroid(
+    position: Vec2,
+    positions: &[Vec2],
+    weighting: CohesionWeighting,
+    crowd_radius: f32,
+) -> Option<Vec2> {
+    let weight = |neighbor: Vec2| match weighting {
+        CohesionWeighting::Uniform => 1.0,
+        CohesionWeighting::Distance => 1.0 / neighbor.distance(position).max(MIN_COHESION_DISTANCE),
+        CohesionWeighting::Density => {
+            let crowd = positions
+                .iter()
+                .filter(|other| other.distance(neighbor) <= crowd_radius)
+                .count();
+            1.0 / crowd.max(1) as f32
+        }
+    };
+    let (sum, total) = positions
+        .iter()
+        .map(|neighbor| (*neighbor, weight(*neighbor)))
+        .fold((Vec2::ZERO, 0.0), |(sum, total), (neighbor, weight)| {
+            (sum + neighbor * weight, total + weight)
+        });
+    if total <= 0.0 {
+        return None;
+    }
+    Some(sum / total)
+}
+
+/// Average of `directions`, `None` if there are none.
+///
+/// Not normalized: headings that disagree average to something shorter, opposite ones to zero.
+pub fn average_direction(directions: &[Vec2]) -> Option<Vec2> {
+    centroid(directions)
+}
+
+/// Average of `speeds`, `None` if there are none.
+pub fn average_speed(speeds: &[f32]) -> Option<f32> {
+    if speeds.is_empty() {
+        return None;
+    }
+    Some(speeds.iter().sum::<f32>() / speeds.len() as f32)
+}
+
+/// Unit vector from `position` away from `neighbors`.
+///
+/// Each neighbor pushes along the direction away from it weighted by one over its distance, so a
+/// boid about to collide outweighs several at the edge of the separation distance.
+///
+/// `None` when there is nobody to separate from, or when the neighbors cancel out around the boid.
+pub fn separation(position: Vec2, neighbors: &[Vec2]) -> Option<Vec2> {
+    let weighted: Vec<(Vec2, f32)> = neighbors.iter().map(|neighbor| (*neighbor, 1.0)).collect();
+    weighted_separation(position, &weighted)
+}
+
+/// [`separation`] from `(position, strength)` pairs, each neighbor's push scaled by its strength.
+///
+/// The result has the length of the average strength, so equal strengths give the same unit
+/// vector as [`separation`] scaled by that strength, and all ones give exactly [`separation`].
+pub fn weighted_separation(position: Vec2, neighbors: &[(Vec2, f32)]) -> Option<Vec2> {
+    let (away, weight, total) = neighbors
+        .iter()
+        .filter_map(|(neighbor, strength)| {
+            let offset = position - *neighbor;
+            // a neighbor exactly on top has no direction to flee in
+            let direction = offset.try_normalize()?;
+            let weight = 1.0 / offset.length().max(MIN_SEPARATION_DISTANCE);
+            Some((direction * weight * *strength, weight * *strength, weight))
+        })
+        .fold((Vec2::ZERO, 0.0, 0.0), |acc, x| {
+            (acc.0 + x.0, acc.1 + x.1, acc.2 + x.2)
+        });
+    // a zero sum would normalize to NaN and poison the direction
+    Some(away.try_normalize()? * weight / total)
+}
+
+/// `speed` moved a `blend` fraction of the way toward `average`, at most all the way.
+pub fn blend_speed(speed: f32, average: f32, blend: f32) -> f32 {
+    speed + (average - speed) * blend.min(1.0)
+}
+
+/// Speed to approach a target `distance` away at, Reynolds' arrive: `cruise` outside
+/// `slowing_radius` and proportionally slower inside it, down to zero on the target.
+///
+/// A `slowing_radius` of zero never slows down.
+pub fn arrival_speed(distance: f32, slowing_radius: f32, cruise: f32) -> f32 {
+    if distance >= slowing_radius {
+        return cruise;
+    }
+    cruise * distance / slowing_radius
+}
+
+/// Moves `target` a `smoothing` fraction of the way toward `average`.
+///
+/// Starts from `average` when there is no target yet, or when it is more than two view distances
+/// away, e.g. after wrapping around the screen. Both are zero while there are no neighbors.
+pub fn smooth_cohesion_target(
+    target: Vec2,
+    average: Vec2,
+    smoothing: f32,
+    view_distance: f32,
+) -> Vec2 {
+    if average == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    if target == Vec2::ZERO || target.distance(average) > 2.0 * view_distance {
+        return average;
+    }
+    target.lerp(average, smoothing.clamp(0.0, 1.0))
+}
+
+/// Moves the smoothed alignment `heading` a `smoothing` fraction of the way toward the neighbors'
+/// `average` direction, an exponential moving average that damps a neighborhood flip-flopping
+/// between two sub-flocks.
+///
+/// Starts out at the `average`, drops back to zero once there is none, and a `smoothing` of 1
+/// follows the `average` exactly.
+pub fn smooth_alignment(heading: Vec2, average: Option<Vec2>, smoothing: f32) -> Vec2 {
+    let Some(average) = average else {
+        return Vec2::ZERO;
+    };
+    if heading == Vec2::ZERO || smoothing >= 1.0 {
+        return average;
+    }
+    heading.lerp(average, smoothing.max(0.0))
+}
+
+/// Weighted sum of the unit vectors toward `cohesion_target`, along the average of `directions`
+/// and away from the `crowding` positions, see [`weighted_separation`] for their strengths.
+///
+/// Each rule contributes at most its weight however many neighbors it sees, so the weights add up
+/// instead of depending on the order the rules are applied in.
+pub fn steering(
+    position: Vec2,
+    cohesion_target: Option<Vec2>,
+    directions: &[Vec2],
+    crowding: &[(Vec2, f32)],
+    weights: SteeringWeights,
+) -> Vec2 {
+    let cohesion =
+        cohesion_target.map_or(Vec2::ZERO, |target| (target - position).normalize_or_zero());
+    let alignment = average_direction(directions)
+        .unwrap_or(Vec2::ZERO)
+        .normalize_or_zero();
+    let separation = weighted_separation(position, crowding).unwrap_or(Vec2::ZERO);
+    cohesion * weights.cohesion + alignment * weights.alignment + separation * weights.separation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_points(rng: &mut StdRng, count: usize) -> Vec<Vec2> {
+        (0..count)
+            .map(|_| Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)))
+            .collect()
+    }
+
+    #[test]
+    fn centroid_of_nothing_is_none() {
+        assert_eq!(centroid(&[]), None);
+    }
+
+    #[test]
+    fn centroid_is_the_average_position() {
+        let positions = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(2.0, 6.0),
+        ];
+        assert_eq!(centroid(&positions), Some(Vec2::new(2.0, 2.0)));
+    }
+
+    #[test]
+    fn centroid_moves_with_the_points() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for count in 1..20 {
+            let points = random_points(&mut rng, count);
+            let offset = Vec2::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0));
+            let moved: Vec<Vec2> = points.iter().map(|point| *point + offset).collect();
+            let expected = centroid(&points).unwrap() + offset;
+            assert!(centroid(&moved).unwrap().abs_diff_eq(expected, 1e-3));
+        }
+    }
+
+    #[test]
+    fn predicted_positions_lead_by_the_velocities() {
+        let positions = [Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)];
+        let velocities = [Vec2::new(0.0, 20.0), Vec2::new(0.0, 10.0)];
+        assert_eq!(
+            predicted_positions(&positions, &velocities, 0.5),
+            [Vec2::new(0.0, 10.0), Vec2::new(10.0, 5.0)]
+        );
+        // no lookahead leaves them where they are
+        assert_eq!(predicted_positions(&positions, &velocities, 0.0), positions);
+    }
+
+    #[test]
+    fn weighted_centroids_favor_the_near_and_the_isolated() {
+        // a lone boid close by on the left, a tight crowd of five further off on the right
+        let lone = Vec2::new(-20.0, 0.0);
+        let crowd = (0..5).map(|i| Vec2::new(40.0, i as f32 - 2.0));
+        let positions: Vec<Vec2> = std::iter::once(lone).chain(crowd).collect();
+        let center = |weighting| weighted_centroid(Vec2::ZERO, &positions, weighting, 10.0);
+
+        let uniform = center(CohesionWeighting::Uniform).unwrap();
+        assert!(uniform.abs_diff_eq(centroid(&positions).unwrap(), 1e-4));
+        assert!((uniform.x - 30.0).abs() < 1e-4, "{uniform}");
+        // each crowd member counts about half as much as the lone boid, twice as close
+        let distance = center(CohesionWeighting::Distance).unwrap();
+        assert!(distance.x < uniform.x, "{distance} vs {uniform}");
+        // the crowd counts as much as the lone boid altogether, halfway between them
+        let density = center(CohesionWeighting::Density).unwrap();
+        assert!((density.x - 10.0).abs() < 1e-3, "{density}");
+
+        for weighting in [CohesionWeighting::Distance, CohesionWeighting::Density] {
+            assert_eq!(weighted_centroid(Vec2::ZERO, &[], weighting, 10.0), None);
+        }
+    }
+
+    #[test]
+    fn opposite_directions_average_to_zero() {
+        assert_eq!(average_direction(&[Vec2::X, Vec2::NEG_X]), Some(Vec2::ZERO));
+        assert_eq!(average_direction(&[]), None);
+    }
+
+    #[test]
+    fn average_direction_is_not_normalized() {
+        let average = average_direction(&[Vec2::X, Vec2::Y]).unwrap();
+        assert_eq!(average, Vec2::new(0.5, 0.5));
+    }
+
+    #[test]
+    fn average_speed_of_neighbors() {
+        assert_eq!(average_speed(&[10.0, 20.0, 30.0]), Some(20.0));
+        assert_eq!(average_speed(&[]), None);
+    }
+
+    #[test]
+    fn separation_points_away_from_a_single_neighbor() {
+        let separation = separation(Vec2::ZERO, &[Vec2::new(0.0, 3.0)]);
+        assert_eq!(separation, Some(Vec2::NEG_Y));
+    }
+
+    #[test]
+    fn separation_is_none_without_neighbors_or_when_they_cancel_out() {
+        assert_eq!(separation(Vec2::ZERO, &[]), None);
+        let around = [Vec2::new(5.0, 0.0), Vec2::new(-5.0, 0.0)];
+        assert_eq!(separation(Vec2::ZERO, &around), None);
+        // a neighbor exactly on top has no direction to flee in either
+        assert_eq!(separation(Vec2::ONE, &[Vec2::ONE]), None);
+    }
+
+    #[test]
+    fn separation_is_a_unit_vector_away_from_some_neighbor() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for count in 1..20 {
+            let position = random_points(&mut rng, 1)[0];
+            let neighbors = random_points(&mut rng, count);
+            let Some(separation) = separation(position, &neighbors) else {
+                continue;
+            };
+            assert!((separation.length() - 1.0).abs() < 1e-5);
+            assert!(neighbors
+                .iter()
+                .any(|neighbor| separation.dot(*neighbor - position) < 0.0));
+        }
+    }
+
+    #[test]
+    fn near_neighbors_push_harder_than_far_ones() {
+        // one neighbor 2 to the right, one 8 above: equal weights would flee along (-1, -1)
+        let near_and_far = [Vec2::new(2.0, 0.0), Vec2::new(0.0, 8.0)];
+        let away = separation(Vec2::ZERO, &near_and_far).unwrap();
+        assert!(away.x < 0.0 && away.y < 0.0);
+        assert!(away.x.abs() > 3.0 * away.y.abs(), "{away}");
+
+        // a neighbor practically on top is clamped instead of dividing by almost zero
+        let close = [Vec2::new(1e-6, 0.0), Vec2::new(0.0, 5.0)];
+        let away = separation(Vec2::ZERO, &close).unwrap();
+        assert!(away.is_finite() && away.x < -0.9, "{away}");
+    }
+
+    #[test]
+    fn blend_speed_stops_at_the_average() {
+        assert_eq!(blend_speed(10.0, 20.0, 0.5), 15.0);
+        assert_eq!(blend_speed(10.0, 20.0, 3.0), 20.0);
+        assert_eq!(blend_speed(10.0, 20.0, 0.0), 10.0);
+    }
+
+    #[test]
+    fn arrival_slows_down_in_proportion_to_the_distance() {
+        assert_eq!(arrival_speed(100.0, 50.0, 20.0), 20.0);
+        assert_eq!(arrival_speed(50.0, 50.0, 20.0), 20.0);
+        assert_eq!(arrival_speed(25.0, 50.0, 20.0), 10.0);
+        assert_eq!(arrival_speed(5.0, 50.0, 20.0), 2.0);
+        assert_eq!(arrival_speed(0.0, 50.0, 20.0), 0.0);
+        // without a radius there is nothing to slow down in
+        assert_eq!(arrival_speed(0.0, 0.0, 20.0), 20.0);
+    }
+
+    #[test]
+    fn cohesion_target_follows_the_average_gradually() {
+        let average = Vec2::new(10.0, 0.0);
+        let target = smooth_cohesion_target(Vec2::new(5.0, 0.0), average, 0.5, 50.0);
+        assert_eq!(target, Vec2::new(7.5, 0.0));
+        // a first target or one left far behind jumps straight to the average
+        assert_eq!(
+            smooth_cohesion_target(Vec2::ZERO, average, 0.5, 50.0),
+            average
+        );
+        assert_eq!(
+            smooth_cohesion_target(Vec2::splat(500.0), average, 0.5, 50.0),
+            average
+        );
+        assert_eq!(
+            smooth_cohesion_target(average, Vec2::ZERO, 0.5, 50.0),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn weaker_neighbors_push_less() {
+        let even = weighted_separation(Vec2::ZERO, &[(Vec2::X, 1.0), (Vec2::Y, 1.0)]).unwrap();
+        let uneven = weighted_separation(Vec2::ZERO, &[(Vec2::X, 1.0), (Vec2::Y, 0.25)]).unwrap();
+        // leans away from the stronger one, and with less than full strength overall
+        assert!(uneven.normalize().x < even.normalize().x, "{uneven}");
+        assert!(uneven.length() < 1.0, "{uneven}");
+        assert_eq!(weighted_separation(Vec2::ZERO, &[(Vec2::X, 0.0)]), None);
+    }
+
+    #[test]
+    fn smoothed_alignment_lags_a_sudden_flip() {
+        let mut heading = smooth_alignment(Vec2::ZERO, Some(Vec2::X), 0.2);
+        assert_eq!(heading, Vec2::X);
+
+        // the neighborhood turns around in one step, the smoothed heading takes a few
+        heading = smooth_alignment(heading, Some(Vec2::NEG_X), 0.2);
+        assert!(heading.x > 0.0, "{heading}");
+        for _ in 0..20 {
+            heading = smooth_alignment(heading, Some(Vec2::NEG_X), 0.2);
+        }
+        assert!(heading.abs_diff_eq(Vec2::NEG_X, 0.05), "{heading}");
+
+        assert_eq!(
+            smooth_alignment(Vec2::X, Some(Vec2::NEG_X), 1.0),
+            Vec2::NEG_X
+        );
+        assert_eq!(smooth_alignment(Vec2::X, None, 0.2), Vec2::ZERO);
+    }
+
+    #[test]
+    fn steering_adds_up_the_weighted_rules() {
+        let weights = SteeringWeights {
+            cohesion: 1.0,
+            alignment: 0.5,
+            separation: 2.0,
+        };
+        let steering = steering(
+            Vec2::ZERO,
+            Some(Vec2::new(10.0, 0.0)),
+            &[Vec2::Y, Vec2::Y],
+            &[(Vec2::new(5.0, 0.0), 1.0)],
+            weights,
+        );
+        // cohesion pulls +x, separation pushes -x twice as hard, alignment points +y
+        assert!(
+            steering.abs_diff_eq(Vec2::new(-1.0, 0.5), 1e-5),
+            "{steering}"
+        );
+    }
+
+    #[test]
+    fn steering_without_neighbors_or_target_is_zero() {
+        let weights = SteeringWeights {
+            cohesion: 1.0,
+            alignment: 1.0,
+            separation: 1.0,
+        };
+        assert_eq!(steering(Vec2::ONE, None, &[], &[], weights), Vec2::ZERO);
+        // standing on the target gives no cohesion direction
+        assert_eq!(
+            steering(Vec2::ONE, Some(Vec2::ONE), &[], &[], weights),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn steering_is_bounded_by_the_sum_of_the_weights() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let weights = SteeringWeights {
+            cohesion: 0.3,
+            alignment: 0.25,
+            separation: 0.5,
+        };
+        for count in 0..20 {
+            let position = random_points(&mut rng, 1)[0];
+            let target = random_points(&mut rng, 1)[0];
+            let directions: Vec<Vec2> = random_points(&mut rng, count)
+                .into_iter()
+                .map(Vec2::normalize_or_zero)
+                .collect();
+            let crowding: Vec<(Vec2, f32)> = random_points(&mut rng, count)
+                .into_iter()
+                .map(|point| (point, 1.0))
+                .collect();
+            let steering = steering(position, Some(target), &directions, &crowding, weights);
+            assert!(steering.is_finite());
+            assert!(steering.length() <= 0.3 + 0.25 + 0.5 + 1e-5);
+        }
+    }
+}
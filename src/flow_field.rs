@@ -0,0 +1,175 @@
+use std::f32::consts::TAU;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{
+    steer_boid, Boid, BoidConfig, DebugDraw, DebugVisualization, SimulationStep, WorldBounds,
+};
+
+/// Distance between the arrows `draw_flow_field_system` draws.
+const ARROW_SPACING: f32 = 40.0;
+const ARROW_LENGTH: f32 = 15.0;
+const ARROW_HEAD: f32 = 4.0;
+const ARROW_COLOR: Color = Color::rgba(0.2, 1.0, 1.0, 0.5);
+
+/// A smoothly varying direction across the world, drifting over time, that boids steer along with
+/// the `flow` weight.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FlowField {
+    /// Noise cells per world unit, smaller values give wider swirls.
+    pub scale: f32,
+    /// Noise cells the field moves through per second, zero freezes it.
+    pub speed: f32,
+    /// Simulated seconds the field has animated for.
+    pub elapsed: f32,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        Self {
+            scale: 0.004,
+            speed: 0.1,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl FlowField {
+    /// Unit direction of the flow at `position`.
+    pub fn at(&self, position: Vec2) -> Vec2 {
+        let sample = (position * self.scale).extend(self.elapsed * self.speed);
+        // noise rarely leaves [-0.5, 0.5], stretch that over a full turn
+        Vec2::from_angle(perlin(sample) * 2.0 * TAU)
+    }
+}
+
+/// Advances the field and turns every boid toward the flow where it is, weighted by `flow`.
+pub fn flow_field_system(
+    mut field: ResMut<FlowField>,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+    time: SimulationStep,
+    config: Res<BoidConfig>,
+) {
+    let delta_seconds = time.delta_seconds();
+    field.elapsed += delta_seconds;
+    if config.flow <= 0.0 {
+        return;
+    }
+    for (transform, mut boid) in boid_query.iter_mut() {
+        let flow = field.at(transform.translation.xy());
+        let weight = boid.rotation_speed * config.flow;
+        steer_boid(&mut boid, flow, weight);
+    }
+}
+
+/// Draws the flow as a grid of arrows over `WorldBounds` while `DebugVisualization::flow_field`
+/// is on.
+pub fn draw_flow_field_system(
+    debug: Res<DebugVisualization>,
+    field: Res<FlowField>,
+    bounds: Res<WorldBounds>,
+    mut lines: DebugDraw,
+) {
+    if !debug.flow_field {
+        return;
+    }
+    let cells = ((bounds.max - bounds.min) / ARROW_SPACING)
+        .ceil()
+        .as_uvec2();
+    for x in 0..cells.x {
+        for y in 0..cells.y {
+            let cell = Vec2::new(x as f32, y as f32) + 0.5;
+            let center = bounds.min + cell * ARROW_SPACING;
+            let flow = field.at(center);
+            let tip = center + flow * ARROW_LENGTH / 2.0;
+            let tail = center - flow * ARROW_LENGTH / 2.0;
+            lines.line_colored(tail.extend(0.0), tip.extend(0.0), ARROW_COLOR);
+            for side in [flow.perp(), -flow.perp()] {
+                let barb = tip + (side - flow) * ARROW_HEAD;
+                lines.line_colored(tip.extend(0.0), barb.extend(0.0), ARROW_COLOR);
+            }
+        }
+    }
+}
+
+/// Ken Perlin's improved gradient noise, zero at every integer lattice point and continuous
+/// in between.
+fn perlin(point: Vec3) -> f32 {
+    let cell = point.floor();
+    let offset = point - cell;
+    let cell = cell.as_ivec3();
+    let fade = offset * offset * offset * (offset * (offset * 6.0 - 15.0) + 10.0);
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let step = IVec3::new(dx, dy, dz);
+        gradient(hash(cell + step), offset - step.as_vec3())
+    };
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), fade.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), fade.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), fade.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), fade.x);
+    lerp(lerp(x00, x10, fade.y), lerp(x01, x11, fade.y), fade.z)
+}
+
+/// Pseudo-random bits for a lattice point, standing in for Perlin's permutation table.
+fn hash(point: IVec3) -> u32 {
+    let [x, y, z] = point.to_array().map(|coordinate| coordinate as u32);
+    let mut hash =
+        x.wrapping_mul(0x8da6_b343) ^ y.wrapping_mul(0xd816_3841) ^ z.wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^ (hash >> 15)
+}
+
+/// Dot product of `offset` with one of the twelve cube edge directions, picked by `hash`.
+fn gradient(hash: u32, offset: Vec3) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { offset.x } else { offset.y };
+    let v = match h {
+        0..=3 => offset.y,
+        12 | 14 => offset.x,
+        _ => offset.z,
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    u + v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_zero_on_the_lattice_and_bounded_between() {
+        for x in -3..3 {
+            for y in -3..3 {
+                assert_eq!(perlin(Vec3::new(x as f32, y as f32, 2.0)), 0.0);
+            }
+        }
+        for i in 0..1000 {
+            let i = i as f32;
+            let point = Vec3::new(i * 0.137, i * 0.071 - 20.0, i * 0.013);
+            let noise = perlin(point);
+            assert!((-1.0..=1.0).contains(&noise), "{noise} at {point}");
+        }
+    }
+
+    #[test]
+    fn flow_changes_smoothly_across_space_and_time() {
+        let mut field = FlowField::default();
+        let position = Vec2::new(300.0, 200.0);
+        let flow = field.at(position);
+        assert!(flow.is_normalized());
+        // a step of a pixel or a sixtieth of a second barely turns it
+        assert!(field.at(position + Vec2::X).angle_between(flow).abs() < 0.1);
+        field.elapsed += 1.0 / 60.0;
+        assert!(field.at(position).angle_between(flow).abs() < 0.1);
+        // but far apart the field points elsewhere
+        let elsewhere = (0..10).any(|i| {
+            let far = position + Vec2::new(i as f32 * 500.0, 0.0);
+            field.at(far).angle_between(flow).abs() > 0.5
+        });
+        assert!(elsewhere);
+    }
+}
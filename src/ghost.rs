@@ -0,0 +1,169 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{Boid, BoundaryConfig, WorldBounds};
+
+/// Opacity of a ghost relative to the boid it copies.
+const GHOST_ALPHA: f32 = 0.35;
+
+/// Across edges in `BoundaryMode::Wrap`, draws a translucent copy of each boid near one where it
+/// will reappear on the opposite side. G toggles it.
+///
+/// Ghosts are only sprites, the simulation never sees them.
+#[derive(Resource)]
+pub struct GhostSettings {
+    pub enabled: bool,
+    /// Boids closer than this to an edge get a ghost.
+    pub margin: f32,
+    /// Most ghosts drawn at once, boids past it go without so dense edges stay cheap.
+    pub max_ghosts: usize,
+}
+
+impl Default for GhostSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            margin: 40.0,
+            max_ghosts: 500,
+        }
+    }
+}
+
+type RealBoid = (With<Boid>, Without<Ghost>);
+
+/// A sprite reused by `ghost_system` to draw one wrapped copy of a boid.
+#[derive(Component)]
+pub struct Ghost;
+
+pub fn toggle_ghosts_system(mut settings: ResMut<GhostSettings>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::G) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+/// Moves the pooled `Ghost` sprites onto the wrapped copies of the boids near an edge, hiding the
+/// ones left over and spawning more only while the pool is smaller than needed.
+pub fn ghost_system(
+    mut commands: Commands,
+    settings: Res<GhostSettings>,
+    boundary: Res<BoundaryConfig>,
+    bounds: Res<WorldBounds>,
+    boid_query: Query<(&Transform, &Sprite, &Handle<Image>), RealBoid>,
+    mut ghost_query: Query<
+        (
+            &mut Transform,
+            &mut Sprite,
+            &mut Handle<Image>,
+            &mut Visibility,
+        ),
+        With<Ghost>,
+    >,
+) {
+    let wrap = boundary.sides.wrapping();
+    let wrapping = settings.enabled && wrap.any();
+    let mut ghosts = boid_query
+        .iter()
+        .filter(|_| wrapping)
+        .flat_map(|(transform, sprite, texture)| {
+            ghost_offsets(transform.translation.xy(), &bounds, wrap, settings.margin)
+                .into_iter()
+                .map(move |offset| {
+                    let mut sprite = sprite.clone();
+                    sprite.color.set_a(sprite.color.a() * GHOST_ALPHA);
+                    let transform = Transform {
+                        translation: transform.translation + offset.extend(0.0),
+                        ..*transform
+                    };
+                    (transform, sprite, texture.clone())
+                })
+        })
+        .take(settings.max_ghosts);
+
+    for (mut transform, mut sprite, mut texture, mut visibility) in ghost_query.iter_mut() {
+        match ghosts.next() {
+            Some((ghost_transform, ghost_sprite, ghost_texture)) => {
+                *transform = ghost_transform;
+                *sprite = ghost_sprite;
+                *texture = ghost_texture;
+                *visibility = Visibility::Inherited;
+            }
+            None if *visibility != Visibility::Hidden => *visibility = Visibility::Hidden,
+            None => {}
+        }
+    }
+    for (transform, sprite, texture) in ghosts {
+        commands.spawn((
+            SpriteBundle {
+                transform,
+                sprite,
+                texture,
+                ..default()
+            },
+            Ghost,
+        ));
+    }
+}
+
+/// Offsets from `position` to where a boid there also shows up across the edges it is within
+/// `margin` of, on the axes that `wrap`, up to three near a corner.
+fn ghost_offsets(position: Vec2, bounds: &WorldBounds, wrap: BVec2, margin: f32) -> Vec<Vec2> {
+    let size = bounds.max - bounds.min;
+    let across = |near_min: bool, near_max: bool, size: f32| match (near_min, near_max) {
+        (true, _) => size,
+        (_, true) => -size,
+        _ => 0.0,
+    };
+    let x = across(
+        wrap.x && position.x - bounds.min.x < margin,
+        wrap.x && bounds.max.x - position.x < margin,
+        size.x,
+    );
+    let y = across(
+        wrap.y && position.y - bounds.min.y < margin,
+        wrap.y && bounds.max.y - position.y < margin,
+        size.y,
+    );
+    let mut offsets = Vec::new();
+    if x != 0.0 {
+        offsets.push(Vec2::new(x, 0.0));
+    }
+    if y != 0.0 {
+        offsets.push(Vec2::new(0.0, y));
+    }
+    if x != 0.0 && y != 0.0 {
+        offsets.push(Vec2::new(x, y));
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghosts_appear_across_each_nearby_edge() {
+        let bounds = WorldBounds {
+            min: Vec2::ZERO,
+            max: Vec2::new(100.0, 50.0),
+        };
+        assert!(ghost_offsets(Vec2::new(50.0, 25.0), &bounds, BVec2::TRUE, 10.0).is_empty());
+        assert_eq!(
+            ghost_offsets(Vec2::new(95.0, 25.0), &bounds, BVec2::TRUE, 10.0),
+            [Vec2::new(-100.0, 0.0)]
+        );
+        // a corner is copied to the two adjacent edges and the diagonal one
+        assert_eq!(
+            ghost_offsets(Vec2::new(5.0, 45.0), &bounds, BVec2::TRUE, 10.0),
+            [
+                Vec2::new(100.0, 0.0),
+                Vec2::new(0.0, -50.0),
+                Vec2::new(100.0, -50.0)
+            ]
+        );
+        // only across the seam when the top and bottom are walls
+        assert_eq!(
+            ghost_offsets(Vec2::new(5.0, 45.0), &bounds, BVec2::new(true, false), 10.0),
+            [Vec2::new(100.0, 0.0)]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{get_random_direction, new_boid, spawn_flock_boid, BoidSpriteHandles, SpawnParams};
+
+/// Which way `spawn_grid` points the boids it places.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GridDirection {
+    /// Every boid flies the same way.
+    Same(Vec2),
+    /// Each boid gets its own random heading, drawn from `RngResource`.
+    #[default]
+    Random,
+    /// Away from the center of the grid, a boid right at the center picks a random heading.
+    RadialOutward,
+}
+
+/// A `rows` by `columns` lattice of boids `spacing` apart, centered on `center`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridLayout {
+    pub rows: usize,
+    pub columns: usize,
+    pub spacing: f32,
+    pub center: Vec2,
+    pub direction: GridDirection,
+}
+
+impl Default for GridLayout {
+    fn default() -> Self {
+        Self {
+            rows: 10,
+            columns: 10,
+            spacing: 20.0,
+            center: Vec2::new(640.0, 360.0),
+            direction: GridDirection::default(),
+        }
+    }
+}
+
+impl GridLayout {
+    /// Lattice points row by row, starting from the bottom left corner.
+    pub fn positions(&self) -> Vec<Vec2> {
+        let size = Vec2::new(
+            self.columns.saturating_sub(1) as f32,
+            self.rows.saturating_sub(1) as f32,
+        ) * self.spacing;
+        let corner = self.center - size / 2.0;
+        (0..self.rows)
+            .flat_map(|row| {
+                (0..self.columns)
+                    .map(move |column| corner + Vec2::new(column as f32, row as f32) * self.spacing)
+            })
+            .collect()
+    }
+
+    fn direction_at(&self, position: Vec2, rng: &mut impl Rng) -> Vec2 {
+        match self.direction {
+            GridDirection::Same(direction) => direction
+                .try_normalize()
+                .unwrap_or_else(|| get_random_direction(rng)),
+            GridDirection::Random => get_random_direction(rng),
+            GridDirection::RadialOutward => (position - self.center)
+                .try_normalize()
+                .unwrap_or_else(|| get_random_direction(rng)),
+        }
+    }
+}
+
+/// Spawns a boid on each point of `layout`, up to `limit` of them, and returns how many it
+/// spawned.
+///
+/// Without `sprites` the boids get no sprite, like `spawn_flock` running headless.
+pub fn spawn_grid(
+    commands: &mut Commands,
+    layout: &GridLayout,
+    sprites: Option<&BoidSpriteHandles>,
+    spawn_params: &SpawnParams,
+    rng: &mut impl Rng,
+    limit: usize,
+) -> usize {
+    let positions = layout.positions();
+    let count = positions.len().min(limit);
+    for position in positions.into_iter().take(count) {
+        let mut boid = new_boid(spawn_params, rng);
+        boid.direction = layout.direction_at(position, rng);
+        spawn_flock_boid(commands, position, boid, sprites, spawn_params);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_positions_form_a_centered_lattice() {
+        let layout = GridLayout {
+            rows: 2,
+            columns: 3,
+            spacing: 10.0,
+            center: Vec2::new(100.0, 50.0),
+            direction: GridDirection::Random,
+        };
+        assert_eq!(
+            layout.positions(),
+            [
+                Vec2::new(90.0, 45.0),
+                Vec2::new(100.0, 45.0),
+                Vec2::new(110.0, 45.0),
+                Vec2::new(90.0, 55.0),
+                Vec2::new(100.0, 55.0),
+                Vec2::new(110.0, 55.0),
+            ]
+        );
+    }
+}
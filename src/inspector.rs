@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::flocking::CohesionWeighting;
+use crate::{Boid, BoidConfig, SpawnParams, Wind};
+
+/// State of the egui tuning panel.
+#[derive(Resource)]
+pub struct InspectorState {
+    pub visible: bool,
+    /// Also write the `SpawnParams` into every existing boid whenever a slider moves.
+    pub broadcast: bool,
+    /// Position of the cohesiveness slider, it overwrites the three flocking weights when moved
+    /// but isn't moved by them, see `BoidConfig::set_cohesiveness`.
+    pub cohesiveness: f32,
+}
+
+impl Default for InspectorState {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            broadcast: false,
+            cohesiveness: 0.5,
+        }
+    }
+}
+
+/// F1 shows or hides the panel.
+pub fn toggle_inspector_system(mut state: ResMut<InspectorState>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::F1) {
+        state.visible = !state.visible;
+    }
+}
+
+/// Sliders for the steering weights and per-boid parameters, requires `bevy_egui::EguiPlugin`.
+pub fn inspector_system(
+    mut contexts: EguiContexts,
+    mut state: ResMut<InspectorState>,
+    mut config: ResMut<BoidConfig>,
+    mut spawn_params: ResMut<SpawnParams>,
+    mut wind: ResMut<Wind>,
+    mut boid_query: Query<(&mut Boid, &mut Transform)>,
+    mut pointer: ResMut<PointerOverInspector>,
+) {
+    if !state.visible {
+        pointer.0 = false;
+        return;
+    }
+
+    let mut boid_params_changed = false;
+    let mut speed_changed = false;
+    egui::Window::new("Boids").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("boids: {}", boid_query.iter().count()));
+
+        ui.heading("Steering");
+        let cohesiveness = egui::Slider::new(&mut state.cohesiveness, 0.0..=1.0)
+            .text("cohesiveness")
+            .custom_formatter(|value, _| format!("{value:.2}"));
+        if ui.add(cohesiveness).changed() {
+            config.set_cohesiveness(state.cohesiveness);
+        }
+        ui.add(egui::Slider::new(&mut config.cohesion, 0.0..=2.0).text("cohesion"));
+        ui.add(egui::Slider::new(&mut config.alignment, 0.0..=2.0).text("alignment"));
+        ui.add(egui::Slider::new(&mut config.speed_alignment, 0.0..=2.0).text("speed alignment"));
+        ui.add(egui::Slider::new(&mut config.separation, 0.0..=2.0).text("separation"));
+        ui.add(egui::Slider::new(&mut config.wander, 0.0..=1.0).text("wander"));
+        ui.add(egui::Slider::new(&mut config.mouse, 0.0..=5.0).text("cursor"));
+        ui.add(egui::Slider::new(&mut config.flow, 0.0..=5.0).text("flow field"));
+        ui.add(egui::Slider::new(&mut config.slowing_radius, 0.0..=200.0).text("slowing radius"));
+        ui.add(
+            egui::Slider::new(&mut config.cohesion_smoothing, 0.0..=1.0).text("cohesion smoothing"),
+        );
+        ui.add(
+            egui::Slider::new(&mut config.alignment_smoothing, 0.0..=1.0)
+                .text("alignment smoothing"),
+        );
+        ui.checkbox(&mut config.predictive_cohesion, "predictive cohesion");
+        ui.add_enabled(
+            config.predictive_cohesion,
+            egui::Slider::new(&mut config.cohesion_lookahead, 0.0..=2.0).text("lookahead"),
+        );
+        ui.add(egui::Slider::new(&mut config.cohesion_dead_zone, 0.0..=20.0).text("dead zone"));
+        ui.horizontal(|ui| {
+            ui.label("cohesion weighting");
+            for (weighting, name) in [
+                (CohesionWeighting::Uniform, "uniform"),
+                (CohesionWeighting::Distance, "distance"),
+                (CohesionWeighting::Density, "density"),
+            ] {
+                ui.selectable_value(&mut config.cohesion_weighting, weighting, name);
+            }
+        });
+        ui.checkbox(&mut config.angular_inertia, "angular inertia");
+        ui.add_enabled(
+            config.angular_inertia,
+            egui::Slider::new(&mut config.angular_acceleration, 0.0..=200.0)
+                .text("turn acceleration"),
+        );
+        ui.add_enabled(
+            config.angular_inertia,
+            egui::Slider::new(&mut config.angular_damping, 0.0..=30.0).text("turn damping"),
+        );
+        ui.add(egui::Slider::new(&mut config.stamina_depletion, 0.0..=1.0).text("stamina use"));
+        ui.add(egui::Slider::new(&mut config.stamina_regen, 0.0..=2.0).text("stamina regen"));
+
+        ui.heading("Wind");
+        ui.add(egui::Slider::new(&mut wind.velocity.x, -100.0..=100.0).text("x"));
+        ui.add(egui::Slider::new(&mut wind.velocity.y, -100.0..=100.0).text("y"));
+
+        ui.heading("New boids");
+        let params = &mut *spawn_params;
+        speed_changed = ui
+            .add(egui::Slider::new(&mut params.speed, 0.0..=200.0).text("speed"))
+            .changed();
+        boid_params_changed |= ui
+            .add(egui::Slider::new(&mut params.rotation_speed, 0.0..=20.0).text("rotation speed"))
+            .changed();
+        boid_params_changed |= ui
+            .add(egui::Slider::new(&mut params.view_distance, 0.0..=300.0).text("view distance"))
+            .changed();
+        boid_params_changed |= ui
+            .add(
+                egui::Slider::new(&mut params.separation_distance, 0.0..=100.0)
+                    .text("separation distance"),
+            )
+            .changed();
+        boid_params_changed |= ui
+            .add(egui::Slider::new(&mut params.radius, 1.0..=20.0).text("radius"))
+            .changed();
+        ui.checkbox(&mut state.broadcast, "apply to existing boids");
+    });
+    pointer.0 = contexts.ctx_mut().is_pointer_over_area();
+
+    if (boid_params_changed || speed_changed) && state.broadcast {
+        for (mut boid, mut transform) in boid_query.iter_mut() {
+            // left alone unless the speed slider moved, so the flock keeps its current and
+            // randomized speeds while other parameters are tuned
+            if speed_changed {
+                boid.speed = spawn_params.speed;
+                // speed only seeds the velocity, so rescale the current one as well
+                boid.velocity = boid.direction * spawn_params.speed;
+            }
+            boid.rotation_speed = spawn_params.rotation_speed;
+            boid.view_distance = spawn_params.view_distance;
+            boid.separation_distance = spawn_params.separation_distance;
+            // relative, so leaders stay larger than the rest
+            transform.scale *= spawn_params.radius / boid.radius;
+            boid.radius = spawn_params.radius;
+        }
+    }
+}
+
+/// Whether the cursor was over an egui area last frame, kept up to date by `inspector_system`.
+#[derive(Resource, Default)]
+pub struct PointerOverInspector(pub bool);
+
+/// Run condition keeping mouse tools from firing through the panel.
+pub fn pointer_outside_inspector(pointer: Option<Res<PointerOverInspector>>) -> bool {
+    !pointer.is_some_and(|pointer| pointer.0)
+}
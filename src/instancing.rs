@@ -0,0 +1,393 @@
+use bevy::asset::load_internal_asset;
+use bevy::core_pipeline::core_2d::Transparent2d;
+use bevy::ecs::query::QueryItem;
+use bevy::ecs::system::lifetimeless::{Read, SRes};
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::mesh::GpuBufferInfo;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_phase::{
+    AddRenderCommand, DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult, RenderPhase,
+    SetItemPipeline, TrackedRenderPass,
+};
+use bevy::render::render_resource::{
+    BlendState, BufferInitDescriptor, BufferUsages, ColorTargetState, ColorWrites, FragmentState,
+    FrontFace, MultisampleState, PipelineCache, PolygonMode, PrimitiveState, PrimitiveTopology,
+    RenderPipelineDescriptor, SpecializedRenderPipeline, SpecializedRenderPipelines, TextureFormat,
+    VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::BevyDefault;
+use bevy::render::view::{ExtractedView, NoFrustumCulling, ViewTarget};
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::sprite::{
+    Mesh2dHandle, Mesh2dPipeline, Mesh2dPipelineKey, Mesh2dUniform, SetMesh2dViewBindGroup,
+};
+use bevy::utils::FloatOrd;
+use bytemuck::{Pod, Zeroable};
+
+use crate::{boid_color, Boid, BoidColorMode, BOID_SPRITE_RADIUS};
+
+const INSTANCED_BOID_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 0x5b1d_70a2_c6e4_9f13);
+
+/// Draws the whole flock as one mesh instanced once per boid, in a single draw call, instead of
+/// a sprite per boid. Needs the `instanced_rendering` feature, add it after `BoidPlugin`.
+///
+/// Boids keep their `Boid` and `Transform`, only the `Sprite` and image they were spawned with are
+/// taken off them. `sync_boid_instances_system` copies every boid's pose and `BoidColorMode`
+/// color into `BoidInstances` each frame, which the render world uploads as one instance buffer.
+/// That skips the per-sprite extraction and batching on the CPU that slows down large flocks,
+/// `examples/render_bench.rs` compares the two.
+pub struct InstancedBoidPlugin;
+
+impl Plugin for InstancedBoidPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            INSTANCED_BOID_SHADER_HANDLE,
+            "instancing.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(ExtractComponentPlugin::<BoidInstances>::default())
+            .add_systems(Startup, spawn_boid_instances)
+            .add_systems(Update, strip_boid_sprites_system)
+            // after `rotate_boid_sprite_system` has turned this frame's transforms
+            .add_systems(PostUpdate, sync_boid_instances_system);
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_command::<Transparent2d, DrawBoidInstances>()
+            .init_resource::<SpecializedRenderPipelines<InstancedBoidPipeline>>()
+            .add_systems(
+                Render,
+                (
+                    prepare_boid_instance_buffers.in_set(RenderSet::Prepare),
+                    queue_boid_instances.in_set(RenderSet::Queue),
+                ),
+            );
+    }
+
+    // the pipeline needs the `RenderDevice`, which only exists once every plugin is built
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<InstancedBoidPipeline>();
+    }
+}
+
+/// What the shader needs to draw one boid.
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct BoidInstance {
+    pub position: [f32; 3],
+    /// Multiplies the mesh, which is as wide as a boid of `BOID_SPRITE_RADIUS`.
+    pub scale: f32,
+    /// Unit vector the mesh's x axis is turned to.
+    pub rotation: [f32; 2],
+    /// Linear RGBA.
+    pub color: [f32; 4],
+}
+
+// SAFETY: plain `f32`s laid out by `repr(C)` without padding, any bit pattern is valid
+unsafe impl Zeroable for BoidInstance {}
+unsafe impl Pod for BoidInstance {}
+
+/// Every boid to draw this frame, on the one entity `InstancedBoidPlugin` spawns.
+#[derive(Component, Clone, Debug, Default)]
+pub struct BoidInstances(pub Vec<BoidInstance>);
+
+impl ExtractComponent for BoidInstances {
+    type Query = &'static BoidInstances;
+    type Filter = ();
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<'_, Self::Query>) -> Option<Self> {
+        Some(item.clone())
+    }
+}
+
+fn spawn_boid_instances(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.spawn((
+        BoidInstances::default(),
+        Mesh2dHandle(meshes.add(boid_mesh())),
+        SpatialBundle::INHERITED_IDENTITY,
+        // the mesh sits at the origin while the instances are all over the world
+        NoFrustumCulling,
+    ));
+}
+
+/// An arrowhead pointing up the y axis, like the boid sprite.
+fn boid_mesh() -> Mesh {
+    let r = BOID_SPRITE_RADIUS;
+    let tip = [0.0, 1.5 * r, 0.0];
+    let notch = [0.0, -0.5 * r, 0.0];
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![tip, [-r, -r, 0.0], notch, tip, notch, [r, -r, 0.0]],
+    );
+    mesh
+}
+
+/// Takes the sprite off boids as they spawn, so the sprite renderer never sees them.
+pub fn strip_boid_sprites_system(
+    mut commands: Commands,
+    boid_query: Query<Entity, (With<Boid>, With<Sprite>)>,
+) {
+    for entity in boid_query.iter() {
+        commands.entity(entity).remove::<(Sprite, Handle<Image>)>();
+    }
+}
+
+/// Rewrites `BoidInstances` from the boids' transforms, colored like `color_boid_system` would.
+pub fn sync_boid_instances_system(
+    mode: Option<Res<BoidColorMode>>,
+    boid_query: Query<(&Transform, &Boid)>,
+    mut instances_query: Query<&mut BoidInstances>,
+) {
+    let Ok(mut instances) = instances_query.get_single_mut() else {
+        return;
+    };
+    let mode = mode.map_or_else(BoidColorMode::default, |mode| *mode);
+    instances.0.clear();
+    instances
+        .0
+        .extend(boid_query.iter().map(|(transform, boid)| BoidInstance {
+            position: transform.translation.to_array(),
+            scale: transform.scale.x,
+            rotation: (transform.rotation * Vec3::X).truncate().to_array(),
+            color: boid_color(mode, boid).as_linear_rgba_f32(),
+        }));
+}
+
+/// The instance buffer uploaded for a `BoidInstances` this frame.
+#[derive(Component)]
+struct BoidInstanceBuffer {
+    buffer: bevy::render::render_resource::Buffer,
+    length: usize,
+}
+
+fn prepare_boid_instance_buffers(
+    mut commands: Commands,
+    instances_query: Query<(Entity, &BoidInstances)>,
+    render_device: Res<RenderDevice>,
+) {
+    for (entity, instances) in instances_query.iter() {
+        if instances.0.is_empty() {
+            continue;
+        }
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("boid instance buffer"),
+            contents: bytemuck::cast_slice(instances.0.as_slice()),
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        });
+        commands.entity(entity).insert(BoidInstanceBuffer {
+            buffer,
+            length: instances.0.len(),
+        });
+    }
+}
+
+fn queue_boid_instances(
+    draw_functions: Res<DrawFunctions<Transparent2d>>,
+    instanced_pipeline: Res<InstancedBoidPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<InstancedBoidPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    msaa: Res<Msaa>,
+    instances_query: Query<(Entity, &BoidInstances, &Mesh2dUniform)>,
+    mut views: Query<(&mut RenderPhase<Transparent2d>, &ExtractedView)>,
+) {
+    let draw_boid_instances = draw_functions.read().id::<DrawBoidInstances>();
+    for (mut transparent_phase, view) in views.iter_mut() {
+        let key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
+            | Mesh2dPipelineKey::from_hdr(view.hdr)
+            | Mesh2dPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        let pipeline = pipelines.specialize(&pipeline_cache, &instanced_pipeline, key);
+        for (entity, instances, uniform) in instances_query.iter() {
+            if instances.0.is_empty() {
+                continue;
+            }
+            transparent_phase.add(Transparent2d {
+                entity,
+                draw_function: draw_boid_instances,
+                pipeline,
+                sort_key: FloatOrd(uniform.transform.w_axis.z),
+                batch_range: None,
+            });
+        }
+    }
+}
+
+#[derive(Resource)]
+struct InstancedBoidPipeline {
+    mesh2d_pipeline: Mesh2dPipeline,
+}
+
+impl FromWorld for InstancedBoidPipeline {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh2d_pipeline: Mesh2dPipeline::from_world(world),
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for InstancedBoidPipeline {
+    type Key = Mesh2dPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let vertex_layout = VertexBufferLayout::from_vertex_formats(
+            VertexStepMode::Vertex,
+            vec![VertexFormat::Float32x3],
+        );
+        // location 0 is the mesh's vertex position
+        let instance_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<BoidInstance>() as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 1,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x2,
+                    offset: 16,
+                    shader_location: 2,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 24,
+                    shader_location: 3,
+                },
+            ],
+        };
+        let format = if key.contains(Mesh2dPipelineKey::HDR) {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            vertex: VertexState {
+                shader: INSTANCED_BOID_SHADER_HANDLE.typed::<Shader>(),
+                entry_point: "vertex".into(),
+                shader_defs: Vec::new(),
+                buffers: vec![vertex_layout, instance_layout],
+            },
+            fragment: Some(FragmentState {
+                shader: INSTANCED_BOID_SHADER_HANDLE.typed::<Shader>(),
+                shader_defs: Vec::new(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            // the instances carry their own transform, so only the view is bound
+            layout: vec![self.mesh2d_pipeline.view_layout.clone()],
+            push_constant_ranges: Vec::new(),
+            primitive: PrimitiveState {
+                front_face: FrontFace::Ccw,
+                // mirrored boids would flip the winding
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+                topology: key.primitive_topology(),
+                strip_index_format: None,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.msaa_samples(),
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            label: Some("instanced_boid_pipeline".into()),
+        }
+    }
+}
+
+type DrawBoidInstances = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    DrawMesh2dInstanced,
+);
+
+struct DrawMesh2dInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawMesh2dInstanced {
+    type Param = SRes<RenderAssets<Mesh>>;
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = (Read<Mesh2dHandle>, Read<BoidInstanceBuffer>);
+
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        (mesh_handle, instances): (&'w Mesh2dHandle, &'w BoidInstanceBuffer),
+        meshes: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(gpu_mesh) = meshes.into_inner().get(&mesh_handle.0) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, instances.buffer.slice(..));
+        let instances = 0..instances.length as u32;
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, instances);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, instances);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boids_are_drawn_as_instances_instead_of_sprites() {
+        let mut app = App::new();
+        app.add_systems(Update, strip_boid_sprites_system)
+            .add_systems(Update, sync_boid_instances_system);
+        let instances = app.world.spawn(BoidInstances::default()).id();
+        let boid = app
+            .world
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(10.0, 20.0, 0.0)
+                        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+                        .with_scale(Vec3::splat(2.0)),
+                    ..default()
+                },
+                Boid::default(),
+            ))
+            .id();
+        app.update();
+
+        assert!(app.world.get::<Sprite>(boid).is_none());
+        let instances = &app.world.get::<BoidInstances>(instances).unwrap().0;
+        assert_eq!(instances.len(), 1);
+        let instance = instances[0];
+        assert_eq!(instance.position, [10.0, 20.0, 0.0]);
+        assert_eq!(instance.scale, 2.0);
+        // turned a quarter to the left, the mesh's x axis points up
+        assert!(Vec2::from_array(instance.rotation).abs_diff_eq(Vec2::Y, 1e-6));
+    }
+}
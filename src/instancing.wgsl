@@ -0,0 +1,40 @@
+// Draws one boid mesh per `BoidInstance`, see `InstancedBoidPlugin`.
+
+#import bevy_sprite::mesh2d_view_bindings view
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    // world position and scale
+    @location(1) position_scale: vec4<f32>,
+    // unit vector the mesh's x axis is turned to
+    @location(2) rotation: vec2<f32>,
+    @location(3) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let local = vertex.position.xy * vertex.position_scale.w;
+    let c = vertex.rotation.x;
+    let s = vertex.rotation.y;
+    let turned = vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c);
+    let world = vec4<f32>(turned + vertex.position_scale.xy, vertex.position_scale.z, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * world;
+    out.color = vertex.color;
+    return out;
+}
+
+struct FragmentInput {
+    @location(0) color: vec4<f32>,
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    return in.color;
+}
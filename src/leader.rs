@@ -0,0 +1,86 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{arrive, sprite_scale, Boid, BoidConfig, WorldCursor};
+
+const LEADER_SCALE: f32 = 1.5;
+
+/// Boids within `attraction_radius` of a leader's `Transform` steer toward it.
+///
+/// Any entity with a `Transform` can lead, not just a boid.
+#[derive(Component)]
+pub struct Leader {
+    pub attraction_radius: f32,
+}
+
+impl Default for Leader {
+    fn default() -> Self {
+        Self {
+            attraction_radius: 200.0,
+        }
+    }
+}
+
+/// Steers every boid that isn't a leader toward the nearest leader in range, slowing down within
+/// `slowing_radius` of it.
+pub fn follow_leader_system(
+    leader_query: Query<(&Transform, &Leader)>,
+    mut boid_query: Query<(&Transform, &mut Boid), Without<Leader>>,
+    config: Res<BoidConfig>,
+) {
+    if leader_query.is_empty() {
+        return;
+    }
+
+    for (transform, mut boid) in boid_query.iter_mut() {
+        let position = transform.translation.xy();
+        let nearest = leader_query
+            .iter()
+            .map(|(leader_transform, leader)| {
+                (leader_transform.translation.xy() - position, leader)
+            })
+            .filter(|(offset, leader)| offset.length() <= leader.attraction_radius)
+            .min_by(|(a, _), (b, _)| a.length_squared().total_cmp(&b.length_squared()));
+
+        if let Some((offset, _)) = nearest {
+            let weight = boid.rotation_speed * config.follow;
+            arrive(&mut boid, offset, config.slowing_radius, weight);
+        }
+    }
+}
+
+/// `L` promotes the boid nearest to the cursor to leader, `K` demotes the leader nearest to it.
+pub fn promote_leader_system(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    keys: Res<Input<KeyCode>>,
+    mut boid_query: Query<(Entity, &mut Transform, &Boid, Option<&Leader>)>,
+) {
+    let promote = keys.just_pressed(KeyCode::L);
+    let demote = !promote && keys.just_pressed(KeyCode::K);
+    if !promote && !demote {
+        return;
+    }
+    let Some(mouse_pos) = cursor.position() else {
+        return;
+    };
+
+    let nearest = boid_query
+        .iter_mut()
+        .filter(|(_, _, _, leader)| leader.is_some() == demote)
+        .min_by(|(_, a, _, _), (_, b, _, _)| {
+            let a = a.translation.xy().distance_squared(mouse_pos);
+            let b = b.translation.xy().distance_squared(mouse_pos);
+            a.total_cmp(&b)
+        });
+
+    if let Some((entity, mut transform, boid, _)) = nearest {
+        if promote {
+            commands.entity(entity).insert(Leader::default());
+            transform.scale = sprite_scale(boid.radius) * LEADER_SCALE;
+        } else {
+            commands.entity(entity).remove::<Leader>();
+            transform.scale = sprite_scale(boid.radius);
+        }
+    }
+}
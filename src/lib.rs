@@ -0,0 +1,2347 @@
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::ecs::system::{EntityCommands, SystemParam};
+use bevy::math::Vec3Swizzles;
+use bevy::utils::HashMap;
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_spatial::kdtree::KDTree2;
+use rand::distributions::Uniform;
+use rand::Rng;
+
+use flocking::smooth_cohesion_target;
+
+mod boundary;
+mod bounds_view;
+mod camera;
+mod clustering;
+mod color;
+mod config;
+mod cursor_repel;
+mod debug;
+mod density;
+mod diagnostics;
+#[cfg(feature = "dim3")]
+mod dim3;
+pub mod flocking;
+mod flow_field;
+mod ghost;
+mod grid_spawn;
+mod inspector;
+#[cfg(feature = "instanced_rendering")]
+mod instancing;
+mod leader;
+mod lifespan;
+mod motion_blur;
+mod mouse;
+mod obstacle;
+mod obstacle_tool;
+mod pause;
+mod pilot;
+mod predator;
+mod recording;
+mod reset;
+mod rng;
+mod scatter;
+mod selection;
+mod snapshot;
+mod spatial;
+mod species;
+mod sprites;
+mod stats;
+mod steering;
+mod time_scale;
+mod trail;
+mod unstack;
+mod wander;
+mod watchdog;
+mod waypoint;
+mod wind;
+mod zone;
+pub use boundary::*;
+pub use bounds_view::*;
+pub use camera::*;
+pub use clustering::*;
+pub use color::*;
+pub use config::*;
+pub use cursor_repel::*;
+pub use debug::*;
+pub use density::*;
+pub use diagnostics::*;
+#[cfg(feature = "dim3")]
+pub use dim3::*;
+pub use flow_field::*;
+pub use ghost::*;
+pub use grid_spawn::*;
+pub use inspector::*;
+#[cfg(feature = "instanced_rendering")]
+pub use instancing::*;
+pub use leader::*;
+pub use lifespan::*;
+pub use motion_blur::*;
+pub use mouse::*;
+pub use obstacle::*;
+pub use obstacle_tool::*;
+pub use pause::*;
+pub use pilot::*;
+pub use predator::*;
+pub use recording::*;
+pub use reset::*;
+pub use rng::*;
+pub use scatter::*;
+pub use selection::*;
+pub use snapshot::*;
+pub use spatial::*;
+pub use species::*;
+pub use sprites::*;
+pub use stats::*;
+pub use steering::*;
+pub use time_scale::*;
+pub use trail::*;
+pub use unstack::*;
+pub use wander::*;
+pub use watchdog::*;
+pub use waypoint::*;
+pub use wind::*;
+pub use zone::*;
+
+const MANUAL_ROTATION_STRENGTH: f32 = 1.0;
+const COHESION_STRENGTH: f32 = 0.2;
+const ALIGNMENT_STRENGTH: f32 = 0.2;
+const SEPARATION_STRENGTH: f32 = 0.35;
+const FLEE_STRENGTH: f32 = 2.0;
+const OBSTACLE_AVOIDANCE_STRENGTH: f32 = 1.5;
+const FOLLOW_STRENGTH: f32 = 0.5;
+const SEEK_STRENGTH: f32 = 0.3;
+const COHESION_SMOOTHING: f32 = 0.1;
+const ALIGNMENT_SMOOTHING: f32 = 1.0;
+const SPEED_ALIGNMENT_STRENGTH: f32 = 0.5;
+const WANDER_STRENGTH: f32 = 0.1;
+const MOUSE_STRENGTH: f32 = 1.0;
+const FLOW_STRENGTH: f32 = 0.0;
+const SLOWING_RADIUS: f32 = 60.0;
+const COHESION_LOOKAHEAD: f32 = 0.5;
+const COHESION_DEAD_ZONE: f32 = 1.0;
+const ANGULAR_ACCELERATION: f32 = 60.0;
+const ANGULAR_DAMPING: f32 = 6.0;
+const STAMINA_DEPLETION: f32 = 0.0;
+const STAMINA_REGEN: f32 = 0.25;
+/// Fraction of `Boid::max_turn_rate` an exhausted boid can still turn at.
+const TIRED_TURN_RATE: f32 = 0.25;
+
+/// Steering weights read by the flocking systems every frame, mutate it at runtime to retune the flock.
+#[derive(Resource, Clone)]
+pub struct BoidConfig {
+    pub manual_rotation: f32,
+    pub cohesion: f32,
+    pub alignment: f32,
+    /// How fast each boid's speed is blended toward the average speed of its neighbors.
+    pub speed_alignment: f32,
+    pub separation: f32,
+    pub flee: f32,
+    pub obstacle_avoidance: f32,
+    pub follow: f32,
+    pub seek: f32,
+    /// Boids seeking the waypoint or following a leader slow down within this distance of it,
+    /// see [`flocking::arrival_speed`], 0 disables it.
+    pub slowing_radius: f32,
+    /// Random turning that keeps the flock from looking perfectly aligned, 0 disables it.
+    pub wander: f32,
+    /// Pull toward or push away from the cursor, see `MouseInfluence`.
+    pub mouse: f32,
+    /// Pull along the `FlowField`, 0 disables it.
+    pub flow: f32,
+    /// Fraction of the way the cohesion target moves toward the neighbors' average each step,
+    /// 1 disables the smoothing.
+    pub cohesion_smoothing: f32,
+    /// Fraction of the way `Boid::alignment_heading` moves toward the neighbors' average
+    /// direction each step, see [`flocking::smooth_alignment`]. 1 disables the smoothing.
+    pub alignment_smoothing: f32,
+    /// Keep separating from boids of other species so flocks don't overlap.
+    pub separate_across_species: bool,
+    /// Steer toward where the neighbors will be `cohesion_lookahead` seconds from now, going by
+    /// their velocities, rather than where they are. Converges more smoothly on a moving flock.
+    pub predictive_cohesion: bool,
+    pub cohesion_lookahead: f32,
+    /// Boids closer than this to their cohesion target get no cohesion at all, so a boid already
+    /// in the middle of its group isn't jerked around by the target's tiny wobbles.
+    pub cohesion_dead_zone: f32,
+    /// How much each neighbor counts toward the cohesion target, weighting by distance or by
+    /// crowding keeps the whole flock from collapsing onto its densest point.
+    pub cohesion_weighting: flocking::CohesionWeighting,
+    /// Turn through `Boid::angular_velocity` instead of straight toward the steered direction,
+    /// so boids swing into a turn and overshoot it a little like birds banking.
+    pub angular_inertia: bool,
+    /// Angular acceleration, in radians per second squared, per radian the heading is off from
+    /// the steered direction.
+    pub angular_acceleration: f32,
+    /// How fast the angular velocity dies down, per second. Below twice the square root of
+    /// `angular_acceleration` the turns overshoot, above it they creep in.
+    pub angular_damping: f32,
+    /// `Boid::stamina` used up per radian the heading turns, 0 keeps every boid fresh.
+    pub stamina_depletion: f32,
+    /// `Boid::stamina` recovered per second, so a boid flying straight gets its turning back.
+    pub stamina_regen: f32,
+    /// Only the nearest this many neighbors of each boid are considered, `None` considers all.
+    ///
+    /// Bounds the per-boid work in dense flocks, where a boid can see hundreds of others. The
+    /// nearest few dominate the steering anyway so the flock looks nearly the same, but boids at
+    /// the center of a crowd lose sight of its far side and cohesion pulls less to the middle.
+    pub max_neighbors: Option<usize>,
+    /// Only the nearest this many boids within the separation distance push a boid away, `None`
+    /// lets all of them.
+    ///
+    /// A collision is only ever about to happen with the closest few, so the avoidance reacts to
+    /// them alone instead of being watered down by the rest of a crowd.
+    pub max_separation_neighbors: Option<usize>,
+}
+
+impl BoidConfig {
+    /// Seconds the cohesion systems extrapolate neighbors by, zero without `predictive_cohesion`.
+    pub fn cohesion_lookahead(&self) -> f32 {
+        if self.predictive_cohesion {
+            self.cohesion_lookahead
+        } else {
+            0.0
+        }
+    }
+
+    /// The `cohesion`, `alignment` and `separation` weights.
+    pub fn steering_weights(&self) -> flocking::SteeringWeights {
+        flocking::SteeringWeights {
+            cohesion: self.cohesion,
+            alignment: self.alignment,
+            separation: self.separation,
+        }
+    }
+
+    /// Sets `cohesion`, `alignment` and `separation` from a single dial between a spread out and
+    /// a tight flock, see [`flocking::SteeringWeights::from_cohesiveness`]. The weights can still
+    /// be tuned one by one afterwards.
+    pub fn set_cohesiveness(&mut self, cohesiveness: f32) {
+        let weights = flocking::SteeringWeights::from_cohesiveness(cohesiveness);
+        self.cohesion = weights.cohesion;
+        self.alignment = weights.alignment;
+        self.separation = weights.separation;
+    }
+}
+
+impl Default for BoidConfig {
+    fn default() -> Self {
+        Self {
+            manual_rotation: MANUAL_ROTATION_STRENGTH,
+            cohesion: COHESION_STRENGTH,
+            alignment: ALIGNMENT_STRENGTH,
+            speed_alignment: SPEED_ALIGNMENT_STRENGTH,
+            separation: SEPARATION_STRENGTH,
+            flee: FLEE_STRENGTH,
+            obstacle_avoidance: OBSTACLE_AVOIDANCE_STRENGTH,
+            follow: FOLLOW_STRENGTH,
+            seek: SEEK_STRENGTH,
+            slowing_radius: SLOWING_RADIUS,
+            wander: WANDER_STRENGTH,
+            mouse: MOUSE_STRENGTH,
+            flow: FLOW_STRENGTH,
+            cohesion_smoothing: COHESION_SMOOTHING,
+            alignment_smoothing: ALIGNMENT_SMOOTHING,
+            separate_across_species: false,
+            predictive_cohesion: false,
+            cohesion_lookahead: COHESION_LOOKAHEAD,
+            cohesion_dead_zone: COHESION_DEAD_ZONE,
+            cohesion_weighting: flocking::CohesionWeighting::Uniform,
+            angular_inertia: false,
+            angular_acceleration: ANGULAR_ACCELERATION,
+            angular_damping: ANGULAR_DAMPING,
+            stamina_depletion: STAMINA_DEPLETION,
+            stamina_regen: STAMINA_REGEN,
+            max_neighbors: None,
+            max_separation_neighbors: None,
+        }
+    }
+}
+
+/// Registers the flocking simulation: spatial tree, steering, movement and sprite rotation.
+///
+/// The camera, mouse spawning and manual rotation are left out so that a consumer can provide
+/// their own; add [`spawn_camera`], [`spawn_boid`], [`despawn_boid_system`], [`spawn_predator`],
+/// [`promote_leader_system`], [`reset_system`], [`rotate_boid_manual_system`] with
+/// [`cycle_player_control_system`] and [`toggle_pause_system`] to opt in. The manual rotation
+/// steers, so it goes in `BoidSet::Steering` of `FixedUpdate`.
+pub struct BoidPlugin;
+
+impl Plugin for BoidPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoidConfig>()
+            .init_resource::<BoundaryConfig>()
+            .init_resource::<SimState>()
+            .init_resource::<SpawnConfig>()
+            .init_resource::<SpawnParams>()
+            .init_resource::<DragSpawn>()
+            .init_resource::<MaxBoids>()
+            .init_resource::<RngResource>()
+            .init_resource::<ScatterTimer>()
+            .init_resource::<SpeciesConfig>()
+            .init_resource::<SeparationMatrix>()
+            .init_resource::<BoidSprites>()
+            .init_resource::<SpriteRotation>()
+            .init_resource::<MouseInfluence>()
+            .init_resource::<CursorRepel>()
+            .init_resource::<NeighborCache>()
+            .init_resource::<SpatialBackend>()
+            .init_resource::<SpatialRefresh>()
+            .init_resource::<NNTree>()
+            .init_resource::<UniformGrid>()
+            .init_resource::<Wind>()
+            .init_resource::<FlowField>()
+            .init_resource::<TimeScale>()
+            .add_event::<BoidEnteredZone>()
+            .add_event::<BoidLeftZone>()
+            .add_systems(PreStartup, init_world_bounds)
+            .add_systems(PreStartup, load_boid_sprites)
+            .add_systems(Startup, spawn_flock)
+            // rebuild the tree in PreUpdate so it reflects the spawns, despawns and movement of
+            // the previous frame before any Update system queries it.
+            // nothing moves while paused, so there is no point rebuilding the tree
+            .add_systems(
+                PreUpdate,
+                rebuild_tree_system
+                    .run_if(simulation_running)
+                    .run_if(kdtree_backend),
+            )
+            // a requested rebuild goes ahead even while paused
+            .add_systems(
+                PreUpdate,
+                forced_rebuild_system
+                    .after(rebuild_tree_system)
+                    .before(update_neighbor_cache_system)
+                    .run_if(kdtree_backend),
+            )
+            .add_systems(
+                PreUpdate,
+                update_grid_system
+                    .before(update_neighbor_cache_system)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                PreUpdate,
+                apply_species_radii_system
+                    .before(update_neighbor_cache_system)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                PreUpdate,
+                update_neighbor_cache_system
+                    .after(rebuild_tree_system)
+                    .run_if(simulation_running),
+            )
+            // steering and movement step by `FixedTime::period` so the flock behaves the same at
+            // any framerate. With the default `SpatialRefresh` `rebuild_tree_system` still rebuilds
+            // the tree once per frame in PreUpdate, as does the neighbor cache, so when a slow
+            // frame runs several fixed steps they all see the positions from the start of that
+            // frame. `TimeScale` shortens or lengthens each step rather than changing how many
+            // run, so the tree and the cache keep the same rate at any scale.
+            .add_systems(
+                FixedUpdate,
+                avoid_walls_system
+                    .in_set(BoidSet::Steering)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                unstack_system
+                    .in_set(BoidSet::Steering)
+                    .before(FlockingSet)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                tick_scatter_timer_system
+                    .in_set(BoidSet::Steering)
+                    .before(FlockingSet)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    boid_flee_system,
+                    avoid_obstacles_system,
+                    follow_leader_system,
+                    seek_waypoint_system,
+                    wander_system,
+                    mouse_influence_system,
+                    cursor_repel_system,
+                    flow_field_system,
+                )
+                    .in_set(BoidSet::Steering)
+                    .after(FlockingSet)
+                    .distributive_run_if(simulation_running),
+            )
+            // movement runs last so it can clamp the step's combined turn to `max_turn_rate`
+            .add_systems(
+                FixedUpdate,
+                move_boid_system
+                    .in_set(BoidSet::Movement)
+                    .run_if(simulation_running),
+            )
+            // catches a heading the steering broke before it is moved along
+            .add_systems(
+                FixedUpdate,
+                watchdog_system
+                    .in_set(BoidSet::Movement)
+                    .before(move_boid_system)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                zone_detection_system
+                    .after(BoidSet::Movement)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                move_obstacles_system
+                    .in_set(BoidSet::Movement)
+                    .before(resolve_obstacle_collisions_system)
+                    .run_if(simulation_running),
+            )
+            .add_systems(
+                FixedUpdate,
+                resolve_obstacle_collisions_system
+                    .in_set(BoidSet::Movement)
+                    .after(move_boid_system)
+                    .run_if(simulation_running),
+            )
+            // a step request advances exactly one fixed step, even if no step runs this frame
+            .add_systems(FixedUpdate, clear_step_system.after(BoidSet::Movement))
+            .add_systems(Update, rotate_boid_sprite_system.in_set(BoidSet::Rendering))
+            .add_systems(Update, aging_system.run_if(simulation_running))
+            .configure_set(FixedUpdate, FlockingSet.in_set(BoidSet::Steering))
+            .configure_sets(FixedUpdate, (BoidSet::Steering, BoidSet::Movement).chain());
+
+        // the original one-system-per-rule flocking, kept around to compare against
+        #[cfg(feature = "sequential_steering")]
+        app.add_systems(
+            FixedUpdate,
+            (
+                boid_cohesion_system,
+                boid_alignment_system,
+                boid_separation_system,
+            )
+                .in_set(FlockingSet)
+                .distributive_run_if(simulation_running),
+        );
+        #[cfg(not(feature = "sequential_steering"))]
+        app.add_systems(
+            FixedUpdate,
+            steering_system
+                .in_set(FlockingSet)
+                .run_if(simulation_running),
+        );
+    }
+}
+
+/// The stages of a simulation step, for ordering your own systems against the flock.
+///
+/// Every fixed step runs all of `Steering` before `Movement`, both in `FixedUpdate`:
+/// steering systems only add the change they want to `Boid::steering`, movement then applies the
+/// sum as one acceleration, clamps the turn and moves the `Transform`. Add a rule to `Steering` and
+/// it is applied in the same step, read the new positions `.after(BoidSet::Movement)`.
+///
+/// `Rendering` runs in `Update`, which comes after every fixed step of the frame, so the
+/// sprites always show the latest positions. Systems that copy a sprite's rotation, such as
+/// [`ghost_system`], go `.after(BoidSet::Rendering)`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoidSet {
+    Steering,
+    Movement,
+    Rendering,
+}
+
+/// Cohesion, alignment and separation, either as `steering_system` or as the three
+/// `sequential_steering` systems. Part of `BoidSet::Steering`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FlockingSet;
+
+pub fn spawn_camera(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
+    let window = window_query.get_single().unwrap();
+
+    commands.spawn(Camera2dBundle {
+        transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
+        camera_2d: Camera2d {
+            //clear_color: ClearColorConfig::None,
+            clear_color: ClearColorConfig::Custom(Color::rgb_u8(64, 18, 18)),
+        },
+        ..default()
+    });
+}
+
+type NNTree = KDTree2<Boid>;
+
+#[derive(Component, Debug)]
+pub struct Boid {
+    /// Cruise speed the steering rules aim for, and the speed a boid spawned without a `velocity`
+    /// starts at.
+    pub speed: f32,
+    /// How hard the steering rules pull the velocity toward what they want, per second, before
+    /// their weights.
+    pub rotation_speed: f32,
+    /// Heading of `velocity`, kept up to date by `move_boid_system`. A boid spawned without a
+    /// velocity starts off along it.
+    pub direction: Vec2,
+    pub view_distance: f32,
+    /// How far cohesion looks for neighbors to steer toward, `None` uses `view_distance`.
+    pub cohesion_radius: Option<f32>,
+    /// How far alignment looks for neighbors to match, `None` uses `view_distance`.
+    pub alignment_radius: Option<f32>,
+    /// Gap separation keeps between the edges of this boid and its neighbors.
+    pub separation_distance: f32,
+    /// Half the width of the boid, its sprite is scaled to match at spawn.
+    pub radius: f32,
+    /// Integrated by `move_boid_system`, its length is kept between `min_speed` and `max_speed`.
+    pub velocity: Vec2,
+    /// Floor on the speed so boids slowed down by the flock never stall.
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Boids only flock with neighbors of the same species.
+    pub species: u32,
+    /// Smoothed average position of the neighbors that cohesion steers toward, zero while the
+    /// boid has no neighbors.
+    pub cohesion_target: Vec2,
+    /// Smoothed average direction of the neighbors that alignment steers along with
+    /// `BoidConfig::alignment_smoothing`, zero while the boid has no neighbors.
+    pub alignment_heading: Vec2,
+    /// Neighbors outside this cone around `direction` are ignored by the flocking systems, 360
+    /// sees all around.
+    pub fov_degrees: f32,
+    /// Most the heading can turn in a second, in radians, however hard the boid is steered. Also
+    /// caps `angular_velocity`. A tired boid turns slower still, see `turn_rate_limit`.
+    pub max_turn_rate: f32,
+    /// Between 0 and 1, worn down by turning and recovered over time with
+    /// `BoidConfig::stamina_depletion` and `stamina_regen`.
+    pub stamina: f32,
+    /// Radians per second the heading is turning at, counterclockwise, with
+    /// `BoidConfig::angular_inertia`.
+    pub angular_velocity: f32,
+    /// Most the velocity can change in a second, however many steering rules pull on it.
+    pub max_force: f32,
+    /// Sum of the changes the steering rules want to make to `velocity`, per second.
+    /// `move_boid_system` applies it as an acceleration limited by `max_force` and clears it.
+    pub steering: Vec2,
+}
+
+impl Default for Boid {
+    fn default() -> Self {
+        Self {
+            speed: 20.0,
+            rotation_speed: 3.0,
+            direction: Vec2::Y,
+            view_distance: 50.0,
+            cohesion_radius: None,
+            alignment_radius: None,
+            separation_distance: 2.0,
+            radius: BOID_SPRITE_RADIUS,
+            velocity: Vec2::ZERO,
+            min_speed: 10.0,
+            max_speed: 40.0,
+            species: 0,
+            cohesion_target: Vec2::ZERO,
+            alignment_heading: Vec2::ZERO,
+            fov_degrees: 360.0,
+            max_turn_rate: 4.0 * std::f32::consts::PI,
+            stamina: 1.0,
+            angular_velocity: 0.0,
+            max_force: 100.0,
+            steering: Vec2::ZERO,
+        }
+    }
+}
+
+impl Boid {
+    pub fn cohesion_radius(&self) -> f32 {
+        self.cohesion_radius.unwrap_or(self.view_distance)
+    }
+
+    pub fn alignment_radius(&self) -> f32 {
+        self.alignment_radius.unwrap_or(self.view_distance)
+    }
+
+    /// `max_turn_rate` scaled down with `stamina`, to a quarter of it once exhausted.
+    pub fn turn_rate_limit(&self) -> f32 {
+        let stamina = self.stamina.clamp(0.0, 1.0);
+        self.max_turn_rate * (TIRED_TURN_RATE + (1.0 - TIRED_TURN_RATE) * stamina)
+    }
+}
+
+/// Per-boid parameters given to every newly spawned boid.
+#[derive(Resource, Clone)]
+pub struct SpawnParams {
+    pub speed: f32,
+    /// Draw each new boid's `speed` uniformly from `(min, max)` instead, so faster boids pull
+    /// ahead of the flock. Speeds outside `Boid::min_speed..=max_speed` are clamped once it moves.
+    pub speed_range: Option<(f32, f32)>,
+    pub rotation_speed: f32,
+    pub view_distance: f32,
+    pub separation_distance: f32,
+    pub radius: f32,
+    /// Seconds each new boid lives before it is despawned, `None` lives forever.
+    pub lifespan: Option<f32>,
+    /// Species of new boids, which also picks their sprite from `BoidSprites`.
+    pub species: u32,
+}
+
+impl Default for SpawnParams {
+    fn default() -> Self {
+        Self {
+            speed: 20.0,
+            speed_range: None,
+            rotation_speed: 3.0,
+            view_distance: 50.0,
+            separation_distance: 2.0,
+            radius: BOID_SPRITE_RADIUS,
+            lifespan: None,
+            species: 0,
+        }
+    }
+}
+
+/// Drag state of `spawn_boid`.
+#[derive(Resource)]
+pub struct DragSpawn {
+    /// World distance the cursor has to travel before the next boid is dropped.
+    pub spacing: f32,
+    /// Where the last boid of the current drag was spawned, `None` while the button is up.
+    pub last: Option<Vec2>,
+}
+
+impl Default for DragSpawn {
+    fn default() -> Self {
+        Self {
+            spacing: 15.0,
+            last: None,
+        }
+    }
+}
+
+/// Spawns a boid when the left button goes down, then another every `DragSpawn::spacing` along
+/// the path while it is dragged, until the flock reaches `MaxBoids`.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_boid(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    sprites: Res<BoidSpriteHandles>,
+    buttons: Res<Input<MouseButton>>,
+    spawn_params: Res<SpawnParams>,
+    mut rng: ResMut<RngResource>,
+    mut drag: ResMut<DragSpawn>,
+    cap: BoidCap,
+) {
+    if buttons.just_released(MouseButton::Left) {
+        drag.last = None;
+        return;
+    }
+    if !buttons.pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(mouse_pos) = cursor.position() else {
+        return;
+    };
+
+    let mut positions = Vec::new();
+    match drag.last {
+        // a fast drag can cover several spacings in one frame, fill the whole path
+        Some(last) if drag.spacing > 0.0 => {
+            let mut last = last;
+            while last.distance(mouse_pos) >= drag.spacing {
+                last += (mouse_pos - last).normalize() * drag.spacing;
+                positions.push(last);
+            }
+            drag.last = Some(last);
+        }
+        Some(_) => {}
+        None => {
+            positions.push(mouse_pos);
+            drag.last = Some(mouse_pos);
+        }
+    }
+
+    let remaining = cap.remaining();
+    if remaining == 0 && buttons.just_pressed(MouseButton::Left) {
+        warn!(
+            "not spawning, the flock already has the maximum of {} boids",
+            cap.max()
+        );
+    }
+    positions.truncate(remaining);
+
+    for position in positions {
+        let boid = new_boid(&spawn_params, &mut rng.0);
+        spawn_flock_boid(&mut commands, position, boid, Some(&sprites), &spawn_params);
+    }
+}
+
+/// Most boids the spawners will create, so a runaway drag can't tank the framerate.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MaxBoids(pub usize);
+
+impl Default for MaxBoids {
+    fn default() -> Self {
+        Self(10_000)
+    }
+}
+
+/// How many more boids may be spawned before reaching `MaxBoids`.
+#[derive(SystemParam)]
+pub struct BoidCap<'w, 's> {
+    max: Res<'w, MaxBoids>,
+    boids: Query<'w, 's, (), With<Boid>>,
+}
+
+impl BoidCap<'_, '_> {
+    pub fn max(&self) -> usize {
+        self.max.0
+    }
+
+    /// Boids despawned or spawned through `Commands` this frame are only counted once applied.
+    pub fn remaining(&self) -> usize {
+        self.max.0.saturating_sub(self.boids.iter().len())
+    }
+}
+
+/// Number of boids `spawn_flock` scatters across `area` at startup.
+#[derive(Resource)]
+pub struct SpawnConfig {
+    pub count: usize,
+    pub area: Rect,
+    /// Lay the flock out on this lattice instead, ignoring `count` and `area`.
+    pub grid: Option<GridLayout>,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            area: Rect::new(0.0, 0.0, 1280.0, 720.0),
+            grid: None,
+        }
+    }
+}
+
+/// Without `BoidSpriteHandles` (e.g. headless under `MinimalPlugins`) the boids get no sprite. At
+/// most `MaxBoids` are spawned.
+pub fn spawn_flock(
+    mut commands: Commands,
+    sprites: Option<Res<BoidSpriteHandles>>,
+    spawn_config: Res<SpawnConfig>,
+    spawn_params: Res<SpawnParams>,
+    mut rng: ResMut<RngResource>,
+    cap: BoidCap,
+) {
+    let sprites = sprites.as_deref();
+    if let Some(grid) = &spawn_config.grid {
+        let total = grid.rows * grid.columns;
+        let count = spawn_grid(
+            &mut commands,
+            grid,
+            sprites,
+            &spawn_params,
+            &mut rng.0,
+            cap.remaining(),
+        );
+        if count < total {
+            warn!(
+                "spawning {count} of {total} grid boids, the cap is {}",
+                cap.max()
+            );
+        }
+        return;
+    }
+
+    let area = spawn_config.area;
+    let count = spawn_config.count.min(cap.remaining());
+    if count < spawn_config.count {
+        warn!(
+            "spawning {count} of {} boids, the cap is {}",
+            spawn_config.count,
+            cap.max()
+        );
+    }
+    for _ in 0..count {
+        let position = Vec2::new(
+            rng.gen_range(area.min.x..=area.max.x),
+            rng.gen_range(area.min.y..=area.max.y),
+        );
+        let boid = new_boid(&spawn_params, &mut rng.0);
+        spawn_flock_boid(&mut commands, position, boid, sprites, &spawn_params);
+    }
+}
+
+/// Spawns `boid` at `position`, with its species' sprite if there are `sprites`.
+fn spawn_flock_boid(
+    commands: &mut Commands,
+    position: Vec2,
+    boid: Boid,
+    sprites: Option<&BoidSpriteHandles>,
+    spawn_params: &SpawnParams,
+) {
+    let mut entity = match sprites {
+        Some(sprites) => {
+            let texture = sprites.get(boid.species);
+            commands.spawn((boid_sprite(position, spawn_params.radius, texture), boid))
+        }
+        None => commands.spawn((
+            TransformBundle::from_transform(
+                Transform::from_xyz(position.x, position.y, 0.0)
+                    .with_scale(sprite_scale(spawn_params.radius)),
+            ),
+            boid,
+        )),
+    };
+    attach_lifespan(&mut entity, spawn_params);
+}
+
+fn attach_lifespan(boid: &mut EntityCommands, spawn_params: &SpawnParams) {
+    if let Some(remaining) = spawn_params.lifespan {
+        boid.insert(Lifespan { remaining });
+    }
+}
+
+/// Half the width of `sprites/boid01.png`, the radius of a boid drawn at scale 1.
+pub const BOID_SPRITE_RADIUS: f32 = 4.0;
+
+/// Scale that draws the boid sprite `radius` wide.
+fn sprite_scale(radius: f32) -> Vec3 {
+    Vec3::splat(radius / BOID_SPRITE_RADIUS)
+}
+
+fn boid_sprite(position: Vec2, radius: f32, texture: Handle<Image>) -> SpriteBundle {
+    SpriteBundle {
+        transform: Transform::from_xyz(position.x, position.y, 0.0)
+            .with_scale(sprite_scale(radius)),
+        texture,
+        sprite: Sprite {
+            color: BOID_COLOR,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn new_boid(spawn_params: &SpawnParams, rng: &mut impl Rng) -> Boid {
+    let speed = match spawn_params.speed_range {
+        Some((min, max)) => rng.gen_range(min..=max),
+        None => spawn_params.speed,
+    };
+    Boid {
+        speed,
+        rotation_speed: spawn_params.rotation_speed,
+        direction: get_random_direction(rng),
+        view_distance: spawn_params.view_distance,
+        separation_distance: spawn_params.separation_distance,
+        radius: spawn_params.radius,
+        species: spawn_params.species,
+        ..default()
+    }
+}
+
+/// Neighbors of every boid, queried once per frame so the flocking systems don't each hit the tree.
+///
+/// Each list holds everything within the largest of `view_distance`, the cohesion and alignment
+/// radii and the separation distance to the largest boid, including the boid itself; systems
+/// needing a smaller radius filter by distance. Across edges in `BoundaryMode::Wrap` neighbors
+/// are stored where they appear from the boid's side, see `BoundaryConfig::wrap_neighbors`.
+#[derive(Resource, Default)]
+pub struct NeighborCache {
+    neighbors: HashMap<Entity, Vec<(Vec2, Option<Entity>)>>,
+}
+
+impl NeighborCache {
+    pub fn get(&self, entity: Entity) -> &[(Vec2, Option<Entity>)] {
+        self.neighbors
+            .get(&entity)
+            .map_or(&[], |neighbors| neighbors)
+    }
+}
+
+pub fn update_neighbor_cache_system(
+    spatial: SpatialQuery,
+    boid_query: Query<(&Transform, &Boid, Entity)>,
+    mut neighbor_cache: ResMut<NeighborCache>,
+    boundary: Res<BoundaryConfig>,
+    bounds: Res<WorldBounds>,
+    config: Res<BoidConfig>,
+) {
+    neighbor_cache.neighbors.clear();
+    let wrap = boundary.sides.wrapping();
+    let wrapped = boundary.wrap_neighbors && wrap.any();
+    let largest = boid_query
+        .iter()
+        .fold(0.0, |largest: f32, (_, boid, _)| largest.max(boid.radius));
+    for (transform, boid, entity) in boid_query.iter() {
+        let radius = boid
+            .view_distance
+            .max(boid.cohesion_radius())
+            .max(boid.alignment_radius())
+            .max(pair_separation_distance(boid, largest));
+        let position = transform.translation.xy();
+        let mut neighbors = if wrapped {
+            spatial.within_distance_wrapped(position, radius, &bounds, wrap)
+        } else {
+            spatial.within_distance(position, radius)
+        };
+        if let Some(max_neighbors) = config.max_neighbors {
+            // the boid itself is in the list too
+            keep_nearest(&mut neighbors, position, max_neighbors + 1);
+        }
+        neighbor_cache.neighbors.insert(entity, neighbors);
+    }
+}
+
+/// Drops all but the `count` points nearest to `position`, in no particular order.
+fn keep_nearest<T>(neighbors: &mut Vec<(Vec2, T)>, position: Vec2, count: usize) {
+    if neighbors.len() <= count {
+        return;
+    }
+    let distance = |(pos, _): &(Vec2, T)| pos.distance_squared(position);
+    if count > 0 {
+        neighbors.select_nth_unstable_by(count - 1, |a, b| distance(a).total_cmp(&distance(b)));
+    }
+    neighbors.truncate(count);
+}
+
+/// How close to the cursor a right click has to be to remove a boid.
+const DESPAWN_RADIUS: f32 = 15.0;
+
+/// Despawns the boid nearest to the cursor on right click, if one is within `DESPAWN_RADIUS`.
+///
+/// The despawn is applied at the end of `Update` and the tree is rebuilt in the next
+/// `PreUpdate`, so the removed boid never shows up in a later neighbor query.
+pub fn despawn_boid_system(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    spatial: SpatialQuery,
+    buttons: Res<Input<MouseButton>>,
+) {
+    if buttons.just_released(MouseButton::Right) {
+        if let Some(mouse_pos) = cursor.position() {
+            if let Some((_, Some(entity))) = spatial.nearest_within(mouse_pos, DESPAWN_RADIUS) {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+pub fn boid_separation_system(
+    neighbor_cache: Res<NeighborCache>,
+    mut boid_query: Query<(&Transform, &mut Boid, Entity), With<Boid>>,
+    config: Res<BoidConfig>,
+    species: Res<SpeciesConfig>,
+    matrix: Res<SeparationMatrix>,
+) {
+    let species_map = species_map(&boid_query);
+    let radius_map: HashMap<Entity, f32> = boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, boid.radius))
+        .collect();
+
+    boid_query
+        .par_iter_mut()
+        .for_each_mut(|(transform, mut boid, entity)| {
+            let position = transform.translation.xy();
+            let mut neighbors: Vec<_> = neighbor_cache
+                .get(entity)
+                .iter()
+                .filter(|(pos, option)| {
+                    let other_radius = option
+                        .and_then(|other| radius_map.get(&other).copied())
+                        .unwrap_or(boid.radius);
+                    pos.distance(position) <= pair_separation_distance(&boid, other_radius)
+                })
+                .filter(|(pos, _)| in_field_of_view(&boid, position, *pos))
+                .copied()
+                .collect();
+            let pair_strength = |option: Option<Entity>| {
+                let other_species = option
+                    .and_then(|other| species_map.get(&other).copied())
+                    .unwrap_or(boid.species);
+                matrix.strength(boid.species, other_species, &config)
+            };
+            neighbors.retain(|(_, option)| pair_strength(*option) > 0.0);
+            if let Some(count) = config.max_separation_neighbors {
+                neighbors.retain(|(_, option)| *option != Some(entity));
+                keep_nearest(&mut neighbors, position, count);
+            }
+            let Some(move_vec) = separation_vector(position, &neighbors, entity, pair_strength)
+            else {
+                return;
+            };
+            let separation = species.weights(boid.species, &config).separation;
+            // the matrix strengths shorten the vector, which the steering would otherwise normalize
+            // away
+            let weight = boid.rotation_speed * separation * move_vec.length();
+            steer_boid(&mut boid, move_vec, weight);
+        });
+}
+
+/// Steers each boid toward the average heading and the average speed of its neighbors, weighted by
+/// `alignment` and `speed_alignment`.
+pub fn boid_alignment_system(
+    neighbor_cache: Res<NeighborCache>,
+    mut boid_query: Query<(&Transform, &mut Boid, Entity), With<Boid>>,
+    config: Res<BoidConfig>,
+    species: Res<SpeciesConfig>,
+) {
+    let direction_map: HashMap<Entity, Vec2> = boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, boid.direction))
+        .collect();
+    let speed_map: HashMap<Entity, f32> = boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, current_speed(boid)))
+        .collect();
+    let species_map = species_map(&boid_query);
+
+    // the maps above are built serially and only read from here on
+    boid_query
+        .par_iter_mut()
+        .for_each_mut(|(transform, mut boid, entity)| {
+            let position = transform.translation.xy();
+            let visible: Vec<_> = neighbor_cache
+                .get(entity)
+                .iter()
+                .filter(|(pos, _)| pos.distance(position) <= boid.alignment_radius())
+                .filter(|(pos, _)| in_field_of_view(&boid, position, *pos))
+                .copied()
+                .collect();
+            if let Some(average_speed) =
+                average_neighbor_speed(&visible, entity, boid.species, &speed_map, &species_map)
+            {
+                let heading = current_velocity(&boid);
+                steer_boid_at(&mut boid, heading, average_speed, config.speed_alignment);
+            }
+
+            let average_direction = average_neighbor_direction(
+                &visible,
+                entity,
+                boid.species,
+                &direction_map,
+                &species_map,
+            );
+            boid.alignment_heading = flocking::smooth_alignment(
+                boid.alignment_heading,
+                average_direction,
+                config.alignment_smoothing,
+            );
+            let alignment = species.weights(boid.species, &config).alignment;
+            let weight = boid.rotation_speed * alignment;
+            let heading = boid.alignment_heading;
+            steer_boid(&mut boid, heading, weight);
+        });
+}
+
+pub fn boid_cohesion_system(
+    neighbor_cache: Res<NeighborCache>,
+    mut boid_query: Query<(&Transform, &mut Boid, Entity), With<Boid>>,
+    config: Res<BoidConfig>,
+    scatter: Res<ScatterTimer>,
+    species: Res<SpeciesConfig>,
+) {
+    let species_map = species_map(&boid_query);
+    let velocity_map: HashMap<Entity, Vec2> = boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, boid.velocity))
+        .collect();
+    let lookahead = config.cohesion_lookahead();
+
+    boid_query
+        .par_iter_mut()
+        .for_each_mut(|(transform, mut boid, entity)| {
+            let position = transform.translation.xy();
+            let mut neighbors = neighbor_cache.get(entity).to_vec();
+            neighbors.retain(|(pos, option)| {
+                is_same_species(&species_map, *option, boid.species)
+                    && pos.distance(position) <= boid.cohesion_radius()
+                    && in_field_of_view(&boid, position, *pos)
+            });
+            if lookahead > 0.0 {
+                let (positions, velocities): (Vec<Vec2>, Vec<Vec2>) = neighbors
+                    .iter()
+                    .map(|(pos, option)| {
+                        let velocity = option.and_then(|other| velocity_map.get(&other).copied());
+                        (*pos, velocity.unwrap_or(Vec2::ZERO))
+                    })
+                    .unzip();
+                let predicted = flocking::predicted_positions(&positions, &velocities, lookahead);
+                for ((pos, _), predicted) in neighbors.iter_mut().zip(predicted) {
+                    *pos = predicted;
+                }
+            }
+
+            // the average snaps whenever a boid enters or leaves view_distance, so steer toward a
+            // target that follows it gradually instead
+            let crowd_radius = cohesion_crowd_radius(&boid);
+            let weighting = config.cohesion_weighting;
+            let avereage_point = calculate_average_point(neighbors, entity, |positions| {
+                flocking::weighted_centroid(position, positions, weighting, crowd_radius)
+            });
+            boid.cohesion_target = smooth_cohesion_target(
+                boid.cohesion_target,
+                avereage_point,
+                config.cohesion_smoothing,
+                boid.view_distance,
+            );
+
+            let vector_to_average_point = Vec2::new(
+                boid.cohesion_target.x - transform.translation.x,
+                boid.cohesion_target.y - transform.translation.y,
+            );
+            if !boid.cohesion_target.eq(&Vec2::ZERO)
+                && vector_to_average_point.length() > config.cohesion_dead_zone
+            {
+                let cohesion = if scatter.active() {
+                    0.0
+                } else {
+                    species.weights(boid.species, &config).cohesion
+                };
+                let weight = boid.rotation_speed * cohesion;
+                steer_boid(&mut boid, vector_to_average_point, weight);
+            }
+        });
+}
+
+/// Accelerates each boid by the `Boid::steering` its rules gave it this step and moves it by its
+/// velocity plus the `Wind` at its position.
+///
+/// The steering is limited to `max_force` per second, so turns are smooth rather than instant,
+/// and the new heading turns no faster than `Boid::turn_rate_limit` allows, or swings there
+/// through `angular_velocity` with `BoidConfig::angular_inertia`. `direction` follows it.
+/// Turning wears down `Boid::stamina`, which in turn lowers how fast the heading can turn. The
+/// wind only displaces the boid, it is not added to `velocity` so it doesn't turn the sprite or
+/// count toward the speed limits.
+pub fn move_boid_system(
+    mut boid_query: Query<(&mut Transform, &mut Boid), With<Boid>>,
+    time: SimulationStep,
+    wind: Res<Wind>,
+    config: Res<BoidConfig>,
+) {
+    let delta_seconds = time.delta_seconds();
+    for (mut transform, mut boid) in boid_query.iter_mut() {
+        if boid.velocity == Vec2::ZERO {
+            boid.velocity = bounded_velocity(&boid);
+        }
+        let heading = boid.velocity.try_normalize().unwrap_or(boid.direction);
+        let steering = std::mem::take(&mut boid.steering);
+        let velocity = accelerate(boid.velocity, steering, boid.max_force, delta_seconds);
+        let target = velocity.try_normalize().unwrap_or(heading);
+        boid.direction = if config.angular_inertia {
+            let (direction, angular_velocity) =
+                turn_with_inertia(heading, target, &boid, &config, delta_seconds);
+            boid.angular_velocity = angular_velocity;
+            direction
+        } else {
+            clamp_turn(heading, target, boid.turn_rate_limit() * delta_seconds)
+        };
+        let turned = heading.angle_between(boid.direction).abs();
+        boid.stamina = tire(boid.stamina, turned, &config, delta_seconds);
+        let speed = velocity.length();
+        boid.velocity = clamp_speed(
+            boid.direction * speed,
+            boid.direction,
+            boid.min_speed,
+            boid.max_speed,
+        );
+        let drift = wind.at(transform.translation.xy());
+        transform.translation += (boid.velocity + drift).extend(0.0) * delta_seconds;
+    }
+}
+
+/// How `rotate_boid_sprite_system` turns the sprites toward their heading.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct SpriteRotation {
+    /// Radians per second the sprite turns toward the heading, `None` snaps it there every frame.
+    pub turn_speed: Option<f32>,
+}
+
+pub fn rotate_boid_sprite_system(
+    mut boid_query: Query<(&mut Transform, &Boid), With<Boid>>,
+    rotation: Res<SpriteRotation>,
+    time: Res<Time>,
+) {
+    for (mut transform, boid) in boid_query.iter_mut() {
+        let heading = boid.velocity.try_normalize().unwrap_or(boid.direction);
+        let target = Quat::from_rotation_arc(Vec3::Y, heading.extend(0.0));
+        transform.rotation = match rotation.turn_speed {
+            Some(turn_speed) => turn_sprite(
+                transform.rotation,
+                target,
+                turn_speed * time.delta_seconds(),
+            ),
+            None => target,
+        };
+    }
+}
+
+/// `current` slerped toward `target` by at most `max_angle` radians.
+fn turn_sprite(current: Quat, target: Quat, max_angle: f32) -> Quat {
+    let angle = current.angle_between(target);
+    if angle <= max_angle {
+        target
+    } else {
+        current.slerp(target, max_angle / angle)
+    }
+}
+
+/// Turns the `PlayerControlled` boids left and right with the arrow keys.
+pub fn rotate_boid_manual_system(
+    mut boid_query: Query<&mut Boid, With<PlayerControlled>>,
+    config: Res<BoidConfig>,
+    keys: Res<Input<KeyCode>>,
+) {
+    // counterclockwise for left, left wins if both are held
+    let turn = if keys.pressed(KeyCode::Left) {
+        1.0
+    } else if keys.pressed(KeyCode::Right) {
+        -1.0
+    } else {
+        return;
+    };
+    for mut boid in boid_query.iter_mut() {
+        let rotation_vector = boid.direction.perp() * turn;
+        let weight = boid.rotation_speed * config.manual_rotation;
+        steer_boid(&mut boid, rotation_vector, weight);
+    }
+}
+
+fn get_random_direction(rng: &mut impl Rng) -> Vec2 {
+    let range = Uniform::new(0.0, 360.0);
+    let random_angle: f32 = rng.sample(range);
+    let random_angle = random_angle.to_radians();
+    Vec2::from_angle(random_angle)
+}
+
+fn rotate_vector(vector: Vec2, angle: f32) -> Vec2 {
+    let cos_theta = angle.cos();
+    let sin_theta = angle.sin();
+
+    let x = vector.x * cos_theta - vector.y * sin_theta;
+    let y = vector.x * sin_theta + vector.y * cos_theta;
+
+    Vec2::new(x, y)
+}
+
+/// `to`, or `from` turned toward it by `max_angle` radians if the turn between them is larger.
+fn clamp_turn(from: Vec2, to: Vec2, max_angle: f32) -> Vec2 {
+    let angle = from.angle_between(to);
+    if !angle.is_finite() || angle.abs() <= max_angle {
+        return to;
+    }
+    rotate_vector(from, max_angle.copysign(angle))
+}
+
+/// `heading` turned by one step of `boid.angular_velocity`, after accelerating it toward `target`
+/// and damping it, along with the new angular velocity.
+fn turn_with_inertia(
+    heading: Vec2,
+    target: Vec2,
+    boid: &Boid,
+    config: &BoidConfig,
+    delta_seconds: f32,
+) -> (Vec2, f32) {
+    let error = heading.angle_between(target);
+    let error = if error.is_finite() { error } else { 0.0 };
+    let accelerated = boid.angular_velocity + error * config.angular_acceleration * delta_seconds;
+    let damped = accelerated * (-config.angular_damping * delta_seconds).exp();
+    let max_turn_rate = boid.turn_rate_limit();
+    let angular_velocity = damped.clamp(-max_turn_rate, max_turn_rate);
+    (
+        rotate_vector(heading, angular_velocity * delta_seconds),
+        angular_velocity,
+    )
+}
+
+/// `stamina` after a step that turned the heading by `turned` radians.
+fn tire(stamina: f32, turned: f32, config: &BoidConfig, delta_seconds: f32) -> f32 {
+    let turned = if turned.is_finite() { turned } else { 0.0 };
+    let spent = config.stamina_depletion * turned;
+    (stamina - spent + config.stamina_regen * delta_seconds).clamp(0.0, 1.0)
+}
+
+/// `average` of the points in `point_list` other than `ignore`, zero if there are none.
+fn calculate_average_point(
+    point_list: Vec<(Vec2, Option<Entity>)>,
+    ignore: Entity,
+    average: impl Fn(&[Vec2]) -> Option<Vec2>,
+) -> Vec2 {
+    // may want to remove the filter so that everyone in the same local group hase the same average point
+    let positions: Vec<Vec2> = point_list
+        .iter()
+        .filter(|(_, entity_option)| *entity_option != Some(ignore))
+        .map(|(position, _)| *position)
+        .collect();
+    average(&positions).unwrap_or(Vec2::ZERO)
+}
+
+/// Neighbors within this distance of one another count as one crowd for
+/// `CohesionWeighting::Density`, half the boid's `cohesion_radius`.
+fn cohesion_crowd_radius(boid: &Boid) -> f32 {
+    boid.cohesion_radius() / 2.0
+}
+
+/// Distance between centers below which `boid` steers away from a neighbor of `other_radius`:
+/// both radii plus the boid's `separation_distance` margin, so large boids keep small ones off.
+fn pair_separation_distance(boid: &Boid, other_radius: f32) -> f32 {
+    boid.radius + other_radius + boid.separation_distance
+}
+
+/// Vector away from the neighbors, ignoring `entity` itself, each pushing as hard as `strength`
+/// says, see [`flocking::weighted_separation`].
+///
+/// `None` when there is nobody to separate from, or when the neighbors cancel out around the boid.
+fn separation_vector(
+    position: Vec2,
+    neighbors: &[(Vec2, Option<Entity>)],
+    entity: Entity,
+    strength: impl Fn(Option<Entity>) -> f32,
+) -> Option<Vec2> {
+    let weighted: Vec<(Vec2, f32)> = neighbors
+        .iter()
+        .filter(|(_, option)| *option != Some(entity))
+        .map(|(pos, option)| (*pos, strength(*option)))
+        .collect();
+    flocking::weighted_separation(position, &weighted)
+}
+
+/// Whether `neighbor` lies within `boid.fov_degrees / 2` of the boid's direction.
+///
+/// A neighbor on top of the boid has no bearing and always counts as visible.
+fn in_field_of_view(boid: &Boid, position: Vec2, neighbor: Vec2) -> bool {
+    if boid.fov_degrees >= 360.0 {
+        return true;
+    }
+    let Some(bearing) = (neighbor - position).try_normalize() else {
+        return true;
+    };
+    let half_fov = (boid.fov_degrees / 2.0).to_radians();
+    boid.direction.angle_between(bearing).abs() <= half_fov
+}
+
+fn species_map(
+    boid_query: &Query<(&Transform, &mut Boid, Entity), With<Boid>>,
+) -> HashMap<Entity, u32> {
+    boid_query
+        .iter()
+        .map(|(_, boid, entity)| (entity, boid.species))
+        .collect()
+}
+
+/// Neighbors without an entity or missing from `species_map` are treated as the same species.
+fn is_same_species(
+    species_map: &HashMap<Entity, u32>,
+    neighbor: Option<Entity>,
+    species: u32,
+) -> bool {
+    neighbor
+        .and_then(|entity| species_map.get(&entity))
+        .is_none_or(|neighbor_species| *neighbor_species == species)
+}
+
+/// Average direction of the same-species neighbors, ignoring `entity` itself.
+fn average_neighbor_direction(
+    neighbors: &[(Vec2, Option<Entity>)],
+    entity: Entity,
+    species: u32,
+    direction_map: &HashMap<Entity, Vec2>,
+    species_map: &HashMap<Entity, u32>,
+) -> Option<Vec2> {
+    let directions: Vec<Vec2> = same_species_neighbors(neighbors, entity, species, species_map)
+        .filter_map(|e| direction_map.get(&e).copied())
+        .collect();
+    flocking::average_direction(&directions)
+}
+
+/// Average speed of the same-species neighbors, ignoring `entity` itself.
+fn average_neighbor_speed(
+    neighbors: &[(Vec2, Option<Entity>)],
+    entity: Entity,
+    species: u32,
+    speed_map: &HashMap<Entity, f32>,
+    species_map: &HashMap<Entity, u32>,
+) -> Option<f32> {
+    let speeds: Vec<f32> = same_species_neighbors(neighbors, entity, species, species_map)
+        .filter_map(|e| speed_map.get(&e).copied())
+        .collect();
+    flocking::average_speed(&speeds)
+}
+
+fn same_species_neighbors<'a>(
+    neighbors: &'a [(Vec2, Option<Entity>)],
+    entity: Entity,
+    species: u32,
+    species_map: &'a HashMap<Entity, u32>,
+) -> impl Iterator<Item = Entity> + 'a {
+    neighbors
+        .iter()
+        .filter_map(|(_, option)| *option)
+        .filter(move |e| e != &entity)
+        .filter(move |e| is_same_species(species_map, Some(*e), species))
+}
+
+fn draw_x(lines: &mut DebugDraw, point: Vec2) {
+    let [x, y] = point.to_array();
+    let left = Vec2::new(x - 3.0, y).extend(0.0);
+    let right = Vec2::new(x + 3.0, y).extend(0.0);
+    let top = Vec2::new(x, y + 3.0).extend(0.0);
+    let bottom = Vec2::new(x, y - 3.0).extend(0.0);
+
+    lines.line(left, right);
+    lines.line(top, bottom);
+}
+
+/// Adds to `boid.steering` the change from its velocity to the desired velocity along
+/// `target_vector` at its cruise `speed`, `weight` times over per second.
+///
+/// This is how every steering rule steers: each asks for its own desired velocity, and
+/// `move_boid_system` applies the sum.
+fn steer_boid(boid: &mut Boid, target_vector: Vec2, weight: f32) {
+    let speed = boid.speed;
+    steer_boid_at(boid, target_vector, speed, weight);
+}
+
+/// [`steer_boid`] with a desired velocity of `speed` rather than the cruise speed.
+fn steer_boid_at(boid: &mut Boid, target_vector: Vec2, speed: f32, weight: f32) {
+    // try_normalize also catches NaN/inf, a zero target would otherwise poison the velocity forever
+    let Some(target_vector) = target_vector.try_normalize() else {
+        return;
+    };
+    let desired = target_vector * speed;
+    boid.steering += (desired - current_velocity(boid)) * weight;
+}
+
+/// Velocity pointing along `boid.direction` with its magnitude clamped to `[min_speed, max_speed]`.
+///
+/// Boids spawned without a velocity get seeded with `direction * speed`.
+fn bounded_velocity(boid: &Boid) -> Vec2 {
+    boid.direction * current_speed(boid).clamp(boid.min_speed, boid.max_speed)
+}
+
+/// `velocity` after `delta_seconds` of `steering`, limited to `max_force`.
+fn accelerate(velocity: Vec2, steering: Vec2, max_force: f32, delta_seconds: f32) -> Vec2 {
+    velocity + steering.clamp_length_max(max_force) * delta_seconds
+}
+
+/// `velocity` with its length clamped to `[min, max]`, along `fallback` if it is zero.
+fn clamp_speed(velocity: Vec2, fallback: Vec2, min: f32, max: f32) -> Vec2 {
+    let heading = velocity.try_normalize().unwrap_or(fallback);
+    heading * velocity.length().clamp(min, max)
+}
+
+/// Steers a boid toward a target `offset` away from it, desiring its arrival speed rather than
+/// the cruise one.
+///
+/// Outside `slowing_radius` that is the cruise `speed`, so boids speed back up when the target
+/// moves away. Movement keeps them at `min_speed` or more.
+fn arrive(boid: &mut Boid, offset: Vec2, slowing_radius: f32, weight: f32) {
+    let speed = flocking::arrival_speed(offset.length(), slowing_radius, boid.speed);
+    steer_boid_at(boid, offset, speed, weight);
+}
+
+/// `velocity`, or `direction * speed` for a boid that hasn't moved yet.
+fn current_velocity(boid: &Boid) -> Vec2 {
+    if boid.velocity == Vec2::ZERO {
+        boid.direction * boid.speed
+    } else {
+        boid.velocity
+    }
+}
+
+/// Length of `velocity`, or `speed` for a boid that hasn't moved yet.
+fn current_speed(boid: &Boid) -> f32 {
+    if boid.velocity == Vec2::ZERO {
+        boid.speed
+    } else {
+        boid.velocity.length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn steer_boid_ignores_zero_target() {
+        let mut boid = Boid {
+            direction: Vec2::new(0.6, 0.8),
+            ..default()
+        };
+        steer_boid(&mut boid, Vec2::ZERO, 0.5);
+        assert_eq!(boid.steering, Vec2::ZERO);
+    }
+
+    #[test]
+    fn steer_boid_ignores_nan_target() {
+        let mut boid = Boid {
+            direction: Vec2::Y,
+            ..default()
+        };
+        steer_boid(&mut boid, Vec2::new(f32::NAN, 1.0), 0.5);
+        assert_eq!(boid.steering, Vec2::ZERO);
+    }
+
+    #[test]
+    fn steer_boid_asks_for_the_desired_velocity_at_the_cruise_speed() {
+        let mut boid = Boid {
+            direction: Vec2::Y,
+            velocity: Vec2::new(0.0, 30.0),
+            ..default()
+        };
+        steer_boid(&mut boid, Vec2::new(5.0, 0.0), 0.5);
+        assert_eq!(boid.steering, Vec2::new(10.0, -15.0));
+        // the rules add up
+        steer_boid(&mut boid, Vec2::NEG_X, 0.5);
+        assert_eq!(boid.steering, Vec2::new(0.0, -30.0));
+    }
+
+    #[test]
+    fn steering_straight_back_brakes_without_losing_the_heading() {
+        let mut boid = Boid {
+            direction: Vec2::Y,
+            ..default()
+        };
+        steer_boid(&mut boid, Vec2::NEG_Y, 0.5);
+        let velocity = accelerate(
+            current_velocity(&boid),
+            boid.steering,
+            boid.max_force,
+            1.0 / 60.0,
+        );
+        assert_eq!(velocity.normalize(), Vec2::Y);
+        assert!(velocity.length() < boid.speed);
+    }
+
+    #[test]
+    fn acceleration_is_limited_by_max_force_and_speed_by_max_speed() {
+        let boid = Boid::default();
+        let turned = accelerate(Vec2::new(20.0, 0.0), Vec2::new(-20.0, 20.0), 5.0, 1.0);
+        assert!((turned - Vec2::new(20.0, 0.0)).length() <= 5.0 + 1e-5);
+
+        let pushed = accelerate(Vec2::new(20.0, 0.0), Vec2::new(980.0, 0.0), 1000.0, 1.0);
+        let clamped = clamp_speed(pushed, Vec2::X, boid.min_speed, boid.max_speed);
+        assert_eq!(clamped, Vec2::new(boid.max_speed, 0.0));
+
+        // braking through zero keeps the boid at min_speed instead of stopping it
+        let stopped = accelerate(Vec2::new(5.0, 0.0), Vec2::new(-10.0, 0.0), 5.0, 2.0);
+        let clamped = clamp_speed(stopped, Vec2::NEG_X, boid.min_speed, boid.max_speed);
+        assert_eq!(clamped, Vec2::new(-boid.min_speed, 0.0));
+    }
+
+    #[test]
+    fn bounded_velocity_seeds_from_speed() {
+        let boid = Boid {
+            direction: Vec2::X,
+            speed: 25.0,
+            ..default()
+        };
+        assert_eq!(bounded_velocity(&boid), Vec2::new(25.0, 0.0));
+    }
+
+    #[test]
+    fn bounded_velocity_clamps_magnitude() {
+        let fast = Boid {
+            direction: Vec2::X,
+            velocity: Vec2::new(100.0, 0.0),
+            ..default()
+        };
+        assert_eq!(bounded_velocity(&fast).length(), fast.max_speed);
+
+        let slow = Boid {
+            direction: Vec2::X,
+            velocity: Vec2::new(1.0, 0.0),
+            ..default()
+        };
+        assert_eq!(bounded_velocity(&slow).length(), slow.min_speed);
+    }
+
+    #[test]
+    fn species_do_not_influence_each_others_alignment() {
+        let me = Entity::from_raw(0);
+        let red = Entity::from_raw(1);
+        let blue = Entity::from_raw(2);
+        let neighbors = vec![
+            (Vec2::new(0.0, 0.0), Some(me)),
+            (Vec2::new(5.0, 0.0), Some(red)),
+            (Vec2::new(-5.0, 0.0), Some(blue)),
+        ];
+        let direction_map =
+            HashMap::from_iter([(me, Vec2::Y), (red, Vec2::X), (blue, Vec2::NEG_X)]);
+        let species_map = HashMap::from_iter([(me, 0), (red, 0), (blue, 1)]);
+
+        let average = average_neighbor_direction(&neighbors, me, 0, &direction_map, &species_map);
+        assert_eq!(average, Some(Vec2::X));
+
+        let only_blue = vec![neighbors[0], neighbors[2]];
+        let average = average_neighbor_direction(&only_blue, me, 0, &direction_map, &species_map);
+        assert_eq!(average, None);
+    }
+
+    #[test]
+    fn spawn_flock_creates_count_boids() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(SpawnConfig {
+                count: 17,
+                ..default()
+            })
+            .init_resource::<SpawnParams>()
+            .init_resource::<MaxBoids>()
+            .insert_resource(RngResource::seeded(0))
+            .add_systems(Startup, spawn_flock);
+        app.update();
+
+        let mut boids = app.world.query_filtered::<(), With<Boid>>();
+        assert_eq!(boids.iter(&app.world).count(), 17);
+    }
+
+    #[test]
+    fn spawn_flock_stops_at_max_boids() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(SpawnConfig {
+                count: 17,
+                ..default()
+            })
+            .init_resource::<SpawnParams>()
+            .insert_resource(MaxBoids(5))
+            .insert_resource(RngResource::seeded(0))
+            .add_systems(Startup, spawn_flock);
+        app.world.spawn(Boid::default());
+        app.update();
+
+        let mut boids = app.world.query_filtered::<(), With<Boid>>();
+        assert_eq!(boids.iter(&app.world).count(), 5);
+    }
+
+    #[test]
+    fn grid_spawn_points_boids_away_from_the_center() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(SpawnConfig {
+                grid: Some(GridLayout {
+                    rows: 3,
+                    columns: 3,
+                    spacing: 10.0,
+                    center: Vec2::ZERO,
+                    direction: GridDirection::RadialOutward,
+                }),
+                ..default()
+            })
+            .init_resource::<SpawnParams>()
+            .init_resource::<MaxBoids>()
+            .insert_resource(RngResource::seeded(0))
+            .add_systems(Startup, spawn_flock);
+        app.update();
+
+        let mut query = app.world.query::<(&Boid, &Transform)>();
+        assert_eq!(query.iter(&app.world).len(), 9);
+        for (boid, transform) in query.iter(&app.world) {
+            let position = transform.translation.xy();
+            if position != Vec2::ZERO {
+                assert!(boid.direction.abs_diff_eq(position.normalize(), 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_spawns_same_directions() {
+        let spawn_directions = |seed| {
+            let mut app = App::new();
+            app.add_plugins(MinimalPlugins)
+                .insert_resource(SpawnConfig {
+                    count: 10,
+                    ..default()
+                })
+                .init_resource::<SpawnParams>()
+                .init_resource::<MaxBoids>()
+                .insert_resource(RngResource::seeded(seed))
+                .add_systems(Startup, spawn_flock);
+            app.update();
+            app.world
+                .query::<(&Transform, &Boid)>()
+                .iter(&app.world)
+                .map(|(transform, boid)| (transform.translation, boid.direction))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(spawn_directions(7), spawn_directions(7));
+        assert_ne!(spawn_directions(7), spawn_directions(8));
+    }
+
+    const STEP: Duration = Duration::from_nanos(16_666_667);
+
+    /// `BoidPlugin` under `MinimalPlugins`, stepped by `run_updates`.
+    fn fixed_step_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(FixedTime::new(STEP))
+            .insert_resource(WorldBounds {
+                min: Vec2::ZERO,
+                max: Vec2::new(1280.0, 720.0),
+            })
+            .insert_resource(RngResource::seeded(0))
+            .add_plugins(BoidPlugin);
+        app
+    }
+
+    /// Runs `updates` frames of exactly one fixed step each, the first update only initializes time.
+    fn run_updates(app: &mut App, updates: usize) {
+        use bevy::time::TimeUpdateStrategy;
+        // `ManualDuration` adds to the wall clock, so advance an explicit instant instead
+        for _ in 0..updates {
+            let now = match app.world.get_resource::<TimeUpdateStrategy>() {
+                Some(TimeUpdateStrategy::ManualInstant(last)) => *last + STEP,
+                _ => bevy::utils::Instant::now(),
+            };
+            app.insert_resource(TimeUpdateStrategy::ManualInstant(now));
+            app.update();
+        }
+    }
+
+    #[test]
+    fn movement_distance_scales_with_the_time_scale() {
+        let distance = |scale: f32| {
+            let mut app = fixed_step_app();
+            // flying straight, so the distance only depends on the steps
+            app.insert_resource(TimeScale(scale))
+                .insert_resource(BoidConfig {
+                    wander: 0.0,
+                    ..default()
+                });
+            let boid = app
+                .world
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(640.0, 360.0, 0.0)),
+                    Boid {
+                        direction: Vec2::X,
+                        ..default()
+                    },
+                ))
+                .id();
+            run_updates(&mut app, 5);
+            app.world.get::<Transform>(boid).unwrap().translation.x - 640.0
+        };
+        let normal = distance(1.0);
+        assert!(normal > 0.0);
+        for scale in [0.5, 2.0, 3.0] {
+            let scaled = distance(scale);
+            assert!(
+                (scaled - normal * scale).abs() < 1e-2,
+                "{scaled} != {normal} * {scale}"
+            );
+        }
+        // zero freezes the flock and a negative scale counts as zero
+        assert_eq!(distance(0.0), 0.0);
+        assert_eq!(distance(-1.0), 0.0);
+    }
+
+    #[test]
+    fn inertia_swings_past_a_sudden_turn_and_settles() {
+        let config = BoidConfig {
+            angular_inertia: true,
+            ..default()
+        };
+        let mut boid = Boid {
+            direction: Vec2::Y,
+            ..default()
+        };
+        let target = Vec2::Y;
+        let mut heading = Vec2::X;
+        let mut angles = Vec::new();
+        for _ in 0..120 {
+            let (turned, angular_velocity) =
+                turn_with_inertia(heading, target, &boid, &config, 1.0 / 60.0);
+            heading = turned;
+            boid.angular_velocity = angular_velocity;
+            angles.push(Vec2::X.angle_between(heading));
+        }
+        let quarter = std::f32::consts::FRAC_PI_2;
+        // far from the instant snap clamp_turn would allow within a single step
+        assert!(angles[0] < quarter / 10.0, "{}", angles[0]);
+        let peak = angles.iter().copied().fold(0.0, f32::max);
+        assert!(peak > quarter + 0.05, "never overshot, peaked at {peak}");
+        let last = angles.last().unwrap();
+        assert!((last - quarter).abs() < 0.01, "still swinging at {last}");
+    }
+
+    #[test]
+    fn cohesiveness_blends_between_the_presets() {
+        let weights = |cohesiveness| {
+            let mut config = BoidConfig::default();
+            config.set_cohesiveness(cohesiveness);
+            flocking::SteeringWeights {
+                cohesion: config.cohesion,
+                alignment: config.alignment,
+                separation: config.separation,
+            }
+        };
+        assert_eq!(weights(0.0), flocking::SteeringWeights::SEPARATED);
+        assert_eq!(weights(1.0), flocking::SteeringWeights::COHESIVE);
+        // out of range is clamped to the nearest preset
+        assert_eq!(weights(-1.0), weights(0.0));
+        assert_eq!(weights(2.0), weights(1.0));
+
+        let halfway = weights(0.5);
+        let default = BoidConfig::default();
+        assert!((halfway.cohesion - default.cohesion).abs() < 1e-6);
+        assert!((halfway.alignment - default.alignment).abs() < 1e-6);
+        assert!((halfway.separation - default.separation).abs() < 1e-6);
+    }
+
+    #[test]
+    fn hard_turns_tire_a_boid_until_it_flies_straight() {
+        let config = BoidConfig {
+            stamina_depletion: 0.1,
+            ..default()
+        };
+        let mut boid = Boid::default();
+        let delta_seconds = 1.0 / 60.0;
+        let mut heading = Vec2::X;
+        // steered straight back every step, so it turns as fast as it is able to
+        let mut turn = |boid: &mut Boid, reverse: bool| {
+            let target = if reverse { -heading } else { heading };
+            let turned = clamp_turn(heading, target, boid.turn_rate_limit() * delta_seconds);
+            let angle = heading.angle_between(turned).abs();
+            boid.stamina = tire(boid.stamina, angle, &config, delta_seconds);
+            heading = turned;
+            angle
+        };
+        let fresh = turn(&mut boid, true);
+        for _ in 0..120 {
+            turn(&mut boid, true);
+        }
+        let tired = turn(&mut boid, true);
+        assert!(boid.stamina < 0.1, "{}", boid.stamina);
+        assert!(tired < fresh / 2.0, "{tired} vs {fresh}");
+
+        // four seconds of flying straight recovers all of it
+        for _ in 0..240 {
+            turn(&mut boid, false);
+        }
+        assert_eq!(boid.stamina, 1.0);
+        assert_eq!(turn(&mut boid, true), fresh);
+    }
+
+    #[test]
+    fn boids_move_one_fixed_step_per_update() {
+        let mut app = fixed_step_app();
+        // flying straight, so the distance only depends on the steps
+        app.insert_resource(BoidConfig {
+            wander: 0.0,
+            ..default()
+        });
+        let boid = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(640.0, 360.0, 0.0)),
+                Boid {
+                    direction: Vec2::X,
+                    ..default()
+                },
+            ))
+            .id();
+        run_updates(&mut app, 3);
+
+        let x = app.world.get::<Transform>(boid).unwrap().translation.x;
+        let expected = 640.0 + 2.0 * Boid::default().speed * STEP.as_secs_f32();
+        assert!((x - expected).abs() < 1e-3, "{x} != {expected}");
+    }
+
+    #[test]
+    fn boids_spawned_on_the_same_spot_drift_apart() {
+        let mut app = fixed_step_app();
+        let boids: Vec<Entity> = (0..2)
+            .map(|_| {
+                app.world
+                    .spawn((
+                        TransformBundle::from_transform(Transform::from_xyz(640.0, 360.0, 0.0)),
+                        Boid {
+                            direction: Vec2::X,
+                            ..default()
+                        },
+                    ))
+                    .id()
+            })
+            .collect();
+        // half a second: the nudge alone only puts them half a pixel apart
+        run_updates(&mut app, 30);
+
+        let position = |entity| app.world.get::<Transform>(entity).unwrap().translation.xy();
+        let distance = position(boids[0]).distance(position(boids[1]));
+        assert!(distance > 2.0, "only {distance} apart");
+    }
+
+    #[test]
+    fn boids_with_a_nan_direction_are_recovered() {
+        let mut app = fixed_step_app();
+        let boid = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(640.0, 360.0, 0.0)),
+                Boid {
+                    direction: Vec2::NAN,
+                    ..default()
+                },
+            ))
+            .id();
+        run_updates(&mut app, 3);
+
+        let boid_state = app.world.get::<Boid>(boid).unwrap();
+        assert!(
+            boid_state.direction.is_normalized(),
+            "{}",
+            boid_state.direction
+        );
+        assert!(boid_state.velocity.is_finite(), "{}", boid_state.velocity);
+        let position = app.world.get::<Transform>(boid).unwrap().translation;
+        assert!(position.is_finite(), "{position}");
+        // and it keeps flying afterwards
+        run_updates(&mut app, 10);
+        let moved = app.world.get::<Transform>(boid).unwrap().translation;
+        assert!(moved.distance(position) > 1.0, "stuck at {moved}");
+    }
+
+    #[test]
+    fn a_forced_rebuild_finds_boids_spawned_since_the_last_one() {
+        let mut app = App::new();
+        app.insert_resource(SpatialRefresh::every(Duration::from_secs(3600)));
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(FixedTime::new(STEP))
+            .insert_resource(WorldBounds {
+                min: Vec2::ZERO,
+                max: Vec2::new(1280.0, 720.0),
+            })
+            .insert_resource(RngResource::seeded(0))
+            .add_plugins(BoidPlugin);
+        let spawn = |app: &mut App, x: f32| {
+            app.world
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(x, 360.0, 0.0)),
+                    Boid::default(),
+                ))
+                .id()
+        };
+        let boid = spawn(&mut app, 640.0);
+        let neighbor = spawn(&mut app, 650.0);
+        let sees_neighbor = |app: &App| {
+            let cache = app.world.resource::<NeighborCache>();
+            cache
+                .get(boid)
+                .iter()
+                .any(|(_, other)| *other == Some(neighbor))
+        };
+
+        // the hourly rebuild hasn't come around yet
+        run_updates(&mut app, 3);
+        assert!(!sees_neighbor(&app));
+
+        app.world.resource_mut::<SpatialRefresh>().rebuild();
+        run_updates(&mut app, 1);
+        assert!(sees_neighbor(&app));
+    }
+
+    #[test]
+    fn boids_inside_an_obstacle_are_pushed_out() {
+        let mut app = fixed_step_app();
+        let center = Vec2::new(640.0, 360.0);
+        app.world.spawn((
+            TransformBundle::from_transform(Transform::from_translation(center.extend(0.0))),
+            Obstacle { radius: 30.0 },
+        ));
+        let boid = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(645.0, 360.0, 0.0)),
+                Boid {
+                    direction: Vec2::NEG_X,
+                    ..default()
+                },
+            ))
+            .id();
+        run_updates(&mut app, 2);
+
+        let position = app.world.get::<Transform>(boid).unwrap().translation.xy();
+        let distance = position.distance(center);
+        assert!(
+            distance >= 30.0 + BOID_SPRITE_RADIUS - 1e-3,
+            "still {distance} from the center"
+        );
+        // and it is no longer heading in
+        let boid = app.world.get::<Boid>(boid).unwrap();
+        assert!(
+            boid.direction.dot(position - center) >= 0.0,
+            "{}",
+            boid.direction
+        );
+    }
+
+    #[test]
+    fn expired_boids_are_gone_from_the_neighbor_cache() {
+        let mut app = fixed_step_app();
+        let spawn = |app: &mut App, x| {
+            app.world
+                .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                    x, 360.0, 0.0,
+                )))
+                .insert(Boid::default())
+                .id()
+        };
+        let survivor = spawn(&mut app, 640.0);
+        let expiring = spawn(&mut app, 645.0);
+        app.world.entity_mut(expiring).insert(Lifespan {
+            remaining: STEP.as_secs_f32() * 1.5,
+        });
+        run_updates(&mut app, 2);
+        assert!(app.world.get_entity(expiring).is_some());
+
+        run_updates(&mut app, 2);
+        assert!(app.world.get_entity(expiring).is_none());
+        let neighbors = app.world.resource::<NeighborCache>().get(survivor);
+        assert_eq!(neighbors, [(neighbors[0].0, Some(survivor))]);
+    }
+
+    #[test]
+    fn stalled_boid_is_bumped_up_to_min_speed_along_its_heading() {
+        let mut app = fixed_step_app();
+        app.insert_resource(BoidConfig {
+            wander: 0.0,
+            ..default()
+        });
+        let boid = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(640.0, 360.0, 0.0)),
+                Boid {
+                    direction: Vec2::X,
+                    velocity: Vec2::new(1e-4, 0.0),
+                    ..default()
+                },
+            ))
+            .id();
+        run_updates(&mut app, 2);
+
+        let boid = app.world.get::<Boid>(boid).unwrap();
+        assert!(
+            (boid.velocity.length() - boid.min_speed).abs() < 1e-4,
+            "{}",
+            boid.velocity
+        );
+        assert!(boid.velocity.normalize().abs_diff_eq(Vec2::X, 1e-5));
+    }
+
+    #[test]
+    fn fast_and_slow_boids_converge_on_a_common_speed() {
+        let mut app = fixed_step_app();
+        app.insert_resource(BoidConfig {
+            cohesion: 0.0,
+            alignment: 0.0,
+            separation: 0.0,
+            speed_alignment: 5.0,
+            ..default()
+        });
+        let spawn = |app: &mut App, y: f32, speed: f32| {
+            app.world.spawn((
+                TransformBundle::from_transform(Transform::from_xyz(640.0, y, 0.0)),
+                Boid {
+                    direction: Vec2::X,
+                    velocity: Vec2::new(speed, 0.0),
+                    ..default()
+                },
+            ));
+        };
+        spawn(&mut app, 360.0, 40.0);
+        for y in [350.0, 355.0, 365.0, 370.0] {
+            spawn(&mut app, y, 10.0);
+        }
+        let spread = |app: &mut App| {
+            let speeds: Vec<f32> = app
+                .world
+                .query::<&Boid>()
+                .iter(&app.world)
+                .map(|boid| boid.velocity.length())
+                .collect();
+            let max = speeds.iter().copied().fold(f32::MIN, f32::max);
+            let min = speeds.iter().copied().fold(f32::MAX, f32::min);
+            max - min
+        };
+
+        run_updates(&mut app, 11);
+        let early = spread(&mut app);
+        run_updates(&mut app, 30);
+        let late = spread(&mut app);
+        assert!(early < 30.0, "{early}");
+        assert!(late < early / 4.0, "{late} vs {early}");
+    }
+
+    #[test]
+    fn spawned_speeds_fall_within_the_speed_range() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(SpawnConfig {
+                count: 100,
+                ..default()
+            })
+            .insert_resource(SpawnParams {
+                speed_range: Some((15.0, 30.0)),
+                ..default()
+            })
+            .init_resource::<MaxBoids>()
+            .insert_resource(RngResource::seeded(0))
+            .add_systems(Startup, spawn_flock);
+        app.update();
+
+        let speeds: Vec<f32> = app
+            .world
+            .query::<&Boid>()
+            .iter(&app.world)
+            .map(|boid| boid.speed)
+            .collect();
+        assert_eq!(speeds.len(), 100);
+        assert!(speeds.iter().all(|speed| (15.0..=30.0).contains(speed)));
+        // spread over the range rather than all the same
+        let slowest = speeds.iter().copied().fold(f32::INFINITY, f32::min);
+        let fastest = speeds.iter().copied().fold(0.0, f32::max);
+        assert!(fastest - slowest > 10.0, "{slowest}..{fastest}");
+    }
+
+    #[test]
+    fn spawned_boids_use_spawn_params() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(SpawnConfig {
+                count: 1,
+                ..default()
+            })
+            .insert_resource(SpawnParams {
+                speed: 42.0,
+                speed_range: None,
+                rotation_speed: 7.0,
+                view_distance: 120.0,
+                separation_distance: 33.0,
+                radius: 8.0,
+                lifespan: Some(5.0),
+                species: 3,
+            })
+            .init_resource::<MaxBoids>()
+            .insert_resource(RngResource::seeded(0))
+            .add_systems(Startup, spawn_flock);
+        app.update();
+
+        let (boid, transform, lifespan) = app
+            .world
+            .query::<(&Boid, &Transform, &Lifespan)>()
+            .single(&app.world);
+        assert_eq!(lifespan.remaining, 5.0);
+        assert_eq!(boid.species, 3);
+        assert_eq!(boid.speed, 42.0);
+        assert_eq!(boid.rotation_speed, 7.0);
+        assert_eq!(boid.view_distance, 120.0);
+        assert_eq!(boid.separation_distance, 33.0);
+        assert_eq!(boid.radius, 8.0);
+        assert_eq!(transform.scale, Vec3::splat(2.0));
+    }
+
+    #[test]
+    fn alignment_identifier_is_spelled_correctly() {
+        // split so this test does not match itself
+        assert!(!include_str!("lib.rs").contains(concat!("ALING", "MENT")));
+        assert_eq!(BoidConfig::default().alignment, ALIGNMENT_STRENGTH);
+    }
+
+    #[test]
+    fn calculate_average_point_keeps_all_none_entries() {
+        let ignore = Entity::from_raw(0);
+        let points = vec![
+            (Vec2::new(0.0, 0.0), None),
+            (Vec2::new(2.0, 0.0), None),
+            (Vec2::new(4.0, 6.0), None),
+        ];
+        assert_eq!(
+            calculate_average_point(points, ignore, flocking::centroid),
+            Vec2::new(2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn calculate_average_point_without_ignored_entity() {
+        let ignore = Entity::from_raw(0);
+        let points = vec![
+            (Vec2::new(1.0, 1.0), Some(Entity::from_raw(1))),
+            (Vec2::new(3.0, 5.0), Some(Entity::from_raw(2))),
+        ];
+        assert_eq!(
+            calculate_average_point(points, ignore, flocking::centroid),
+            Vec2::new(2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn cohesion_target_moves_gradually() {
+        let start = Vec2::new(100.0, 100.0);
+        let average = Vec2::new(110.0, 100.0);
+
+        let target = smooth_cohesion_target(start, average, 0.1, 50.0);
+        assert!(target.x > start.x && target.x < average.x);
+        assert!((target.x - 101.0).abs() < 1e-4);
+
+        let target = smooth_cohesion_target(target, average, 0.1, 50.0);
+        assert!(target.x > 101.0 && target.x < average.x);
+    }
+
+    #[test]
+    fn cohesion_target_starts_at_the_average() {
+        let average = Vec2::new(110.0, 100.0);
+        assert_eq!(
+            smooth_cohesion_target(Vec2::ZERO, average, 0.1, 50.0),
+            average
+        );
+        // too far away to be the same group, e.g. after wrapping
+        assert_eq!(
+            smooth_cohesion_target(Vec2::new(1000.0, 100.0), average, 0.1, 50.0),
+            average
+        );
+        assert_eq!(
+            smooth_cohesion_target(average, Vec2::ZERO, 0.1, 50.0),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn neighbor_behind_is_outside_a_90_degree_fov() {
+        let boid = Boid {
+            direction: Vec2::Y,
+            fov_degrees: 90.0,
+            ..default()
+        };
+        let position = Vec2::new(10.0, 10.0);
+        assert!(!in_field_of_view(&boid, position, Vec2::new(10.0, 0.0)));
+        assert!(in_field_of_view(&boid, position, Vec2::new(12.0, 20.0)));
+
+        let all_around = Boid {
+            fov_degrees: 360.0,
+            ..boid
+        };
+        assert!(in_field_of_view(
+            &all_around,
+            position,
+            Vec2::new(10.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn symmetric_neighbors_do_not_separate() {
+        let me = Entity::from_raw(0);
+        let position = Vec2::new(10.0, 10.0);
+        let neighbors = vec![
+            (position, Some(me)),
+            (Vec2::new(5.0, 10.0), Some(Entity::from_raw(1))),
+            (Vec2::new(15.0, 10.0), Some(Entity::from_raw(2))),
+        ];
+        assert_eq!(separation_vector(position, &neighbors, me, |_| 1.0), None);
+
+        let mut boid = Boid {
+            direction: Vec2::Y,
+            ..default()
+        };
+        if let Some(move_vec) = separation_vector(position, &neighbors, me, |_| 1.0) {
+            steer_boid(&mut boid, move_vec, 0.5);
+        }
+        assert!(boid.steering.is_finite());
+    }
+
+    #[test]
+    fn single_neighbor_without_self_separates() {
+        let me = Entity::from_raw(0);
+        let position = Vec2::new(10.0, 10.0);
+        // the cache doesn't have to contain the boid itself
+        let neighbors = vec![(Vec2::new(15.0, 10.0), Some(Entity::from_raw(1)))];
+        assert_eq!(
+            separation_vector(position, &neighbors, me, |_| 1.0),
+            Some(Vec2::NEG_X)
+        );
+
+        let only_self = vec![(position, Some(me)), (position, Some(me))];
+        assert_eq!(separation_vector(position, &only_self, me, |_| 1.0), None);
+    }
+
+    #[test]
+    fn turn_toward_opposite_heading_is_clamped() {
+        let cap = 0.1;
+        let turned = clamp_turn(Vec2::X, Vec2::NEG_X, cap);
+        assert!((Vec2::X.angle_between(turned).abs() - cap).abs() < 1e-5);
+        assert!((turned.length() - 1.0).abs() < 1e-5);
+
+        let small = Vec2::from_angle(0.05);
+        assert_eq!(clamp_turn(Vec2::X, small, cap), small);
+    }
+
+    #[test]
+    fn sprite_turns_at_most_max_angle_per_frame() {
+        let target = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let turned = turn_sprite(Quat::IDENTITY, target, 0.1);
+        assert!((Quat::IDENTITY.angle_between(turned) - 0.1).abs() < 1e-4);
+        assert_eq!(turn_sprite(turned, target, 10.0), target);
+    }
+
+    #[test]
+    fn random_directions_are_unit_vectors() {
+        let mut rng = RngResource::seeded(3).0;
+        for _ in 0..1000 {
+            let direction = get_random_direction(&mut rng);
+            assert!((direction.length() - 1.0).abs() < 1e-5, "{direction}");
+        }
+    }
+
+    #[test]
+    fn random_directions_spread_evenly_around_the_circle() {
+        let mut rng = RngResource::seeded(4).0;
+        let samples = 10_000;
+        let mut quadrants = [0; 4];
+        let mut sum = Vec2::ZERO;
+        for _ in 0..samples {
+            let direction = get_random_direction(&mut rng);
+            sum += direction;
+            let quadrant = match (direction.x >= 0.0, direction.y >= 0.0) {
+                (true, true) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+                (true, false) => 3,
+            };
+            quadrants[quadrant] += 1;
+        }
+        // each axis of the mean of n uniform unit vectors has a standard deviation of 1 / sqrt(2n)
+        let mean = sum / samples as f32;
+        assert!(mean.length() < 0.03, "mean {mean}");
+        for count in quadrants {
+            assert!((2300..2700).contains(&count), "{quadrants:?}");
+        }
+    }
+
+    #[test]
+    fn keep_nearest_drops_the_furthest_neighbors() {
+        let mut neighbors: Vec<_> = [5.0, 1.0, 4.0, 0.0, 3.0]
+            .into_iter()
+            .map(|x| (Vec2::new(x, 0.0), None::<Entity>))
+            .collect();
+        keep_nearest(&mut neighbors, Vec2::ZERO, 3);
+        let mut kept: Vec<f32> = neighbors.iter().map(|(pos, _)| pos.x).collect();
+        kept.sort_by(f32::total_cmp);
+        assert_eq!(kept, [0.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn calculate_average_point_only_self_is_zero() {
+        let ignore = Entity::from_raw(0);
+        let points = vec![(Vec2::new(5.0, 5.0), Some(ignore))];
+        assert_eq!(
+            calculate_average_point(points, ignore, flocking::centroid),
+            Vec2::ZERO
+        );
+    }
+}
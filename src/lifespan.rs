@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+
+use crate::TimeScale;
+
+/// Seconds left before `aging_system` despawns the entity.
+///
+/// `spawn_boid` and `spawn_flock` attach one to every new boid when `SpawnParams::lifespan` is
+/// set, so continuous spawning settles into a steady population.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Lifespan {
+    pub remaining: f32,
+}
+
+/// Counts every `Lifespan` down by the frame time times the `TimeScale` and despawns the entities
+/// that reach zero.
+///
+/// The despawns are applied at the end of `Update`, before the next frame rebuilds the
+/// tree and the neighbor cache, so expired boids never turn up in a neighbor query.
+pub fn aging_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Lifespan)>,
+    time: Res<Time>,
+    scale: Res<TimeScale>,
+) {
+    for (entity, mut lifespan) in query.iter_mut() {
+        lifespan.remaining -= time.delta_seconds() * scale.get();
+        if lifespan.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
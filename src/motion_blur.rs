@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{Boid, BoidSet, SimState};
+
+/// Most copies kept behind each boid, whatever `MotionBlurSettings::length` asks for.
+pub const MAX_MOTION_BLUR_LENGTH: usize = 16;
+/// Copies are drawn this far behind the boids so the boid itself stays on top.
+const COPY_Z_OFFSET: f32 = -0.5;
+
+/// Draws faded copies of every boid's sprite where and how it was turned over the last few
+/// frames, so turns smear like a long exposure. Unlike the `TrailPlugin` lines the copies keep
+/// the sprite's orientation. V toggles it.
+#[derive(Default)]
+pub struct MotionBlurPlugin;
+
+impl Plugin for MotionBlurPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MotionBlurSettings>()
+            .add_systems(Update, toggle_motion_blur_system)
+            .add_systems(Update, attach_motion_history_system)
+            .add_systems(
+                Update,
+                motion_blur_system
+                    .after(toggle_motion_blur_system)
+                    .after(attach_motion_history_system)
+                    .after(BoidSet::Rendering),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct MotionBlurSettings {
+    pub enabled: bool,
+    /// Copies drawn behind each boid, one per frame, capped at `MAX_MOTION_BLUR_LENGTH`.
+    pub length: usize,
+    /// Opacity of the newest copy relative to the boid, the older ones fade out from it.
+    pub alpha: f32,
+}
+
+impl Default for MotionBlurSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: 6,
+            alpha: 0.5,
+        }
+    }
+}
+
+impl MotionBlurSettings {
+    fn length(&self) -> usize {
+        self.length.min(MAX_MOTION_BLUR_LENGTH)
+    }
+}
+
+/// Where a boid was and how its sprite was turned over the last frames, oldest first.
+///
+/// Lives on the boid, so despawning the boid drops its history and its copies stop being drawn.
+#[derive(Component, Default)]
+pub struct MotionHistory {
+    pub poses: VecDeque<(Vec2, Quat)>,
+}
+
+impl MotionHistory {
+    fn push(&mut self, pose: (Vec2, Quat), len: usize) {
+        self.poses.push_back(pose);
+        while self.poses.len() > len {
+            self.poses.pop_front();
+        }
+    }
+}
+
+/// A sprite reused by `motion_blur_system` to draw one faded copy of a boid.
+#[derive(Component)]
+pub struct MotionBlurCopy;
+
+type RealBoid = (With<Boid>, Without<MotionBlurCopy>);
+
+pub fn toggle_motion_blur_system(
+    mut settings: ResMut<MotionBlurSettings>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::V) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+/// Gives every boid without one a `MotionHistory`.
+pub fn attach_motion_history_system(
+    mut commands: Commands,
+    boid_query: Query<Entity, (With<Boid>, Without<MotionHistory>)>,
+) {
+    for entity in boid_query.iter() {
+        commands.entity(entity).insert(MotionHistory::default());
+    }
+}
+
+/// Moves the pooled `MotionBlurCopy` sprites onto each boid's past poses, fading toward the
+/// oldest, then records where the boid is now. Copies left over are hidden and more are only
+/// spawned while the pool is smaller than needed.
+///
+/// While paused nothing is recorded, the copies stay where they were. Turning it off clears the
+/// histories so stale poses don't flash up when it is turned back on.
+pub fn motion_blur_system(
+    mut commands: Commands,
+    settings: Res<MotionBlurSettings>,
+    sim_state: Res<SimState>,
+    mut boid_query: Query<(&Transform, &Sprite, &Handle<Image>, &mut MotionHistory), RealBoid>,
+    mut copy_query: Query<
+        (
+            &mut Transform,
+            &mut Sprite,
+            &mut Handle<Image>,
+            &mut Visibility,
+        ),
+        With<MotionBlurCopy>,
+    >,
+) {
+    if !settings.enabled {
+        for (.., mut history) in boid_query.iter_mut() {
+            history.poses.clear();
+        }
+    }
+    let len = settings.length();
+    let mut copies = Vec::new();
+    for (transform, sprite, texture, history) in boid_query.iter() {
+        let count = history.poses.len();
+        for (i, (position, rotation)) in history.poses.iter().enumerate() {
+            let mut sprite = sprite.clone();
+            sprite
+                .color
+                .set_a(sprite.color.a() * settings.alpha * copy_alpha(i, count));
+            let copy_transform = Transform {
+                translation: position.extend(transform.translation.z + COPY_Z_OFFSET),
+                rotation: *rotation,
+                scale: transform.scale,
+            };
+            copies.push((copy_transform, sprite, texture.clone()));
+        }
+    }
+
+    let mut copies = copies.into_iter();
+    for (mut transform, mut sprite, mut texture, mut visibility) in copy_query.iter_mut() {
+        match copies.next() {
+            Some((copy_transform, copy_sprite, copy_texture)) => {
+                *transform = copy_transform;
+                *sprite = copy_sprite;
+                *texture = copy_texture;
+                *visibility = Visibility::Inherited;
+            }
+            None if *visibility != Visibility::Hidden => *visibility = Visibility::Hidden,
+            None => {}
+        }
+    }
+    for (transform, sprite, texture) in copies {
+        commands.spawn((
+            SpriteBundle {
+                transform,
+                sprite,
+                texture,
+                ..default()
+            },
+            MotionBlurCopy,
+        ));
+    }
+
+    let running = !sim_state.paused || sim_state.step;
+    if settings.enabled && running {
+        for (transform, .., mut history) in boid_query.iter_mut() {
+            history.push((transform.translation.xy(), transform.rotation), len);
+        }
+    }
+}
+
+/// Opacity of the `index`th of `count` copies, oldest first, relative to the newest.
+fn copy_alpha(index: usize, count: usize) -> f32 {
+    (index + 1) as f32 / count as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible_copies(app: &mut App) -> Vec<(Vec2, Quat, f32)> {
+        let mut query = app
+            .world
+            .query_filtered::<(&Transform, &Sprite, &Visibility), With<MotionBlurCopy>>();
+        query
+            .iter(&app.world)
+            .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+            .map(|(transform, sprite, _)| {
+                (
+                    transform.translation.xy(),
+                    transform.rotation,
+                    sprite.color.a(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn copies_follow_past_poses_and_go_with_their_boid() {
+        let mut app = App::new();
+        app.init_resource::<SimState>()
+            .init_resource::<Input<KeyCode>>()
+            .insert_resource(MotionBlurSettings {
+                enabled: true,
+                length: 2,
+                alpha: 0.5,
+            })
+            .add_plugins(MotionBlurPlugin);
+        let boid = app
+            .world
+            .spawn((
+                SpriteBundle::default(),
+                Boid::default(),
+                MotionHistory::default(),
+            ))
+            .id();
+        let poses = [
+            (Vec2::new(0.0, 0.0), Quat::IDENTITY),
+            (Vec2::new(5.0, 0.0), Quat::from_rotation_z(0.5)),
+            (Vec2::new(10.0, 0.0), Quat::from_rotation_z(1.0)),
+            (Vec2::new(15.0, 0.0), Quat::from_rotation_z(1.5)),
+        ];
+        for (position, rotation) in poses {
+            let mut transform = app.world.get_mut::<Transform>(boid).unwrap();
+            transform.translation = position.extend(0.0);
+            transform.rotation = rotation;
+            app.update();
+        }
+        // only the last two poses are kept, each copy turned like the boid was then and the
+        // older one fainter
+        app.update();
+        let mut copies = visible_copies(&mut app);
+        copies.sort_by(|a, b| a.0.x.total_cmp(&b.0.x));
+        assert_eq!(copies.len(), 2);
+        assert_eq!(copies[0].0, poses[2].0);
+        assert!(copies[0].1.abs_diff_eq(poses[2].1, 1e-6));
+        assert!(copies[1].1.abs_diff_eq(poses[3].1, 1e-6));
+        assert!(copies[0].2 < copies[1].2);
+        assert_eq!(copies[1].2, 0.5);
+
+        app.world.despawn(boid);
+        app.update();
+        assert!(visible_copies(&mut app).is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{steer_boid, Boid, BoidConfig, SpatialQuery, WorldCursor};
+
+/// What the cursor does to nearby boids.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MouseMode {
+    #[default]
+    None,
+    Attract,
+    Repel,
+}
+
+/// The cursor as a point of interest, boids within `radius` of it are steered by
+/// `mouse_influence_system`.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MouseInfluence {
+    pub mode: MouseMode,
+    pub radius: f32,
+    /// Cursor world position, kept up to date by `track_mouse_influence_system`.
+    pub position: Option<Vec2>,
+}
+
+impl Default for MouseInfluence {
+    fn default() -> Self {
+        Self {
+            mode: MouseMode::default(),
+            radius: 150.0,
+            position: None,
+        }
+    }
+}
+
+/// M cycles the mode between none, attract and repel; the cursor position is stored every frame
+/// so the fixed step systems can read it.
+pub fn track_mouse_influence_system(
+    mut influence: ResMut<MouseInfluence>,
+    cursor: WorldCursor,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::M) {
+        influence.mode = match influence.mode {
+            MouseMode::None => MouseMode::Attract,
+            MouseMode::Attract => MouseMode::Repel,
+            MouseMode::Repel => MouseMode::None,
+        };
+    }
+    influence.position = cursor.position();
+}
+
+/// Turns the boids within the influence radius toward or away from the cursor, weighted by
+/// `mouse`.
+///
+/// Repulsion grows toward the cursor like fleeing from a predator, attraction is the same
+/// everywhere inside the radius.
+pub fn mouse_influence_system(
+    influence: Res<MouseInfluence>,
+    spatial: SpatialQuery,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+    config: Res<BoidConfig>,
+) {
+    let Some(cursor) = influence.position else {
+        return;
+    };
+    if influence.mode == MouseMode::None || influence.radius <= 0.0 {
+        return;
+    }
+
+    for (_, entity) in spatial.within_distance(cursor, influence.radius) {
+        let Some(entity) = entity else { continue };
+        let Ok((transform, mut boid)) = boid_query.get_mut(entity) else {
+            continue;
+        };
+        let offset = transform.translation.xy() - cursor;
+        let (target, weight) = match influence.mode {
+            MouseMode::Attract => (-offset, 1.0),
+            MouseMode::Repel => (offset, 1.0 - (offset.length() / influence.radius).min(1.0)),
+            MouseMode::None => continue,
+        };
+        let weight = boid.rotation_speed * config.mouse * weight;
+        steer_boid(&mut boid, target, weight);
+    }
+}
@@ -0,0 +1,289 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{current_speed, steer_boid, Boid, BoidConfig, SimulationStep};
+
+/// Circular collider centered on the entity's `Transform`, static unless it also has an
+/// `ObstacleVelocity`.
+#[derive(Component)]
+pub struct Obstacle {
+    pub radius: f32,
+}
+
+/// World units per second `move_obstacles_system` moves an `Obstacle` by.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ObstacleVelocity(pub Vec2);
+
+/// Moves every obstacle with an `ObstacleVelocity` along it, ahead of the collision check.
+pub fn move_obstacles_system(
+    mut obstacle_query: Query<(&mut Transform, &ObstacleVelocity), With<Obstacle>>,
+    time: SimulationStep,
+) {
+    let delta_seconds = time.delta_seconds();
+    for (mut transform, velocity) in obstacle_query.iter_mut() {
+        transform.translation += (velocity.0 * delta_seconds).extend(0.0);
+    }
+}
+
+/// Steers boids around obstacles their heading would run into within `view_distance`.
+///
+/// Moving obstacles are dodged where they will be rather than where they are: the boid looks
+/// along its velocity relative to the obstacle, as far as it would see ahead in that time.
+pub fn avoid_obstacles_system(
+    obstacle_query: Query<(&Transform, &Obstacle, Option<&ObstacleVelocity>)>,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+    config: Res<BoidConfig>,
+) {
+    for (transform, mut boid) in boid_query.iter_mut() {
+        let position = transform.translation.xy();
+        let heading = boid.velocity.try_normalize().unwrap_or(boid.direction);
+        let boid_velocity = heading * current_speed(&boid);
+        let steering = obstacle_query
+            .iter()
+            .filter_map(|(obstacle_transform, obstacle, velocity)| {
+                let velocity = velocity.map_or(Vec2::ZERO, |velocity| velocity.0);
+                let (direction, lookahead) =
+                    relative_lookahead(boid.direction, boid_velocity, boid.view_distance, velocity);
+                let center = obstacle_transform.translation.xy();
+                (center.distance(position) <= lookahead + obstacle.radius).then(|| {
+                    obstacle_avoidance(position, direction, lookahead, center, obstacle.radius)
+                })
+            })
+            .fold(Vec2::ZERO, |acc, x| acc + x);
+
+        let urgency = steering.length().min(1.0);
+        if urgency > 0.0 {
+            let weight = boid.rotation_speed * config.obstacle_avoidance * urgency;
+            steer_boid(&mut boid, steering, weight);
+        }
+    }
+}
+
+/// Puts boids that ended the step inside an obstacle, or passed through one, back on its surface
+/// and bounces their heading off it.
+///
+/// A safety net behind `avoid_obstacles_system` for boids too fast or too boxed in to steer
+/// clear. The path of the step is taken to be a straight line back along the velocity, wind
+/// drift aside, so a thin obstacle jumped over in one step still stops the boid.
+pub fn resolve_obstacle_collisions_system(
+    obstacle_query: Query<(&Transform, &Obstacle, Option<&ObstacleVelocity>), Without<Boid>>,
+    mut boid_query: Query<(&mut Transform, &mut Boid)>,
+    time: SimulationStep,
+) {
+    let delta_seconds = time.delta_seconds();
+    for (mut transform, mut boid) in boid_query.iter_mut() {
+        for (obstacle_transform, obstacle, velocity) in obstacle_query.iter() {
+            // seen from the obstacle, which has already moved this step
+            let velocity = velocity.map_or(Vec2::ZERO, |velocity| velocity.0);
+            let end = transform.translation.xy();
+            let start = end - (boid.velocity - velocity) * delta_seconds;
+            let center = obstacle_transform.translation.xy();
+            let Some((contact, normal)) =
+                obstacle_contact(start, end, center, obstacle.radius + boid.radius)
+            else {
+                continue;
+            };
+            transform.translation = contact.extend(transform.translation.z);
+            boid.direction = reflect(boid.direction, normal);
+            boid.velocity = reflect(boid.velocity, normal);
+        }
+    }
+}
+
+/// Where a boid moving from `start` to `end` touches the circle around `center`, and the
+/// surface normal there.
+///
+/// The entry point if the path crosses into the circle, otherwise the nearest surface point to
+/// an `end` inside it. `None` if the boid stays outside.
+fn obstacle_contact(start: Vec2, end: Vec2, center: Vec2, radius: f32) -> Option<(Vec2, Vec2)> {
+    let path = end - start;
+    let from_center = start - center;
+    let a = path.length_squared();
+    let b = 2.0 * from_center.dot(path);
+    let c = from_center.length_squared() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if c >= 0.0 && a > 0.0 && discriminant >= 0.0 {
+        let t = (-b - discriminant.sqrt()) / (2.0 * a);
+        if (0.0..=1.0).contains(&t) {
+            let contact = start + path * t;
+            return Some((contact, (contact - center) / radius));
+        }
+    }
+    if end.distance(center) >= radius {
+        return None;
+    }
+    // dead center has no way out, back out the way it came
+    let normal = (end - center)
+        .try_normalize()
+        .or_else(|| (-path).try_normalize())
+        .unwrap_or(Vec2::Y);
+    Some((center + normal * radius, normal))
+}
+
+/// `vector` mirrored off a surface with unit `normal`, unchanged if it already points away.
+fn reflect(vector: Vec2, normal: Vec2) -> Vec2 {
+    let into = vector.dot(normal);
+    if into >= 0.0 {
+        return vector;
+    }
+    vector - 2.0 * into * normal
+}
+
+/// Direction and distance a boid moving at `velocity` covers, relative to an obstacle moving at
+/// `obstacle_velocity`, in the time it takes to cross `view_distance`.
+///
+/// A static obstacle, or a boid standing still, always gives back `direction` and
+/// `view_distance`.
+fn relative_lookahead(
+    direction: Vec2,
+    velocity: Vec2,
+    view_distance: f32,
+    obstacle_velocity: Vec2,
+) -> (Vec2, f32) {
+    let speed = velocity.length();
+    if obstacle_velocity == Vec2::ZERO || speed <= 0.0 {
+        return (direction, view_distance);
+    }
+    let relative = velocity - obstacle_velocity;
+    let seconds = view_distance / speed;
+    // pacing the obstacle exactly leaves no direction and so nothing to dodge
+    (relative.normalize_or_zero(), relative.length() * seconds)
+}
+
+/// Steering away from an obstacle the ray `position + direction * t` hits within `lookahead`.
+///
+/// Points sideways from the obstacle center and grows from 0 at `lookahead` to 1 at the obstacle
+/// surface, zero when the path is clear.
+fn obstacle_avoidance(
+    position: Vec2,
+    direction: Vec2,
+    lookahead: f32,
+    center: Vec2,
+    radius: f32,
+) -> Vec2 {
+    let to_center = center - position;
+    let along = to_center.dot(direction);
+    let surface_distance = (to_center.length() - radius).max(0.0);
+    if along < 0.0 || surface_distance > lookahead {
+        return Vec2::ZERO; // behind us or too far ahead
+    }
+
+    let closest_point = position + direction * along;
+    let offset = closest_point - center;
+    if offset.length() >= radius {
+        return Vec2::ZERO; // path passes beside the obstacle
+    }
+
+    // dead center hits have no sideways offset, pick a side
+    let away = offset.try_normalize().unwrap_or(direction.perp());
+    let urgency = if lookahead > 0.0 {
+        1.0 - surface_distance / lookahead
+    } else {
+        1.0
+    };
+    away * urgency
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeScale;
+
+    #[test]
+    fn boid_aimed_at_obstacle_center_is_steered() {
+        let steering = obstacle_avoidance(Vec2::ZERO, Vec2::X, 50.0, Vec2::new(30.0, 0.0), 10.0);
+        assert!(steering.length() > 0.0);
+        // the correction has to turn the boid, not just push it backward
+        assert!(steering.perp_dot(Vec2::X).abs() > 0.0);
+    }
+
+    #[test]
+    fn clear_path_has_no_steering() {
+        let steering = obstacle_avoidance(Vec2::ZERO, Vec2::X, 50.0, Vec2::new(30.0, 20.0), 10.0);
+        assert_eq!(steering, Vec2::ZERO);
+    }
+
+    #[test]
+    fn a_path_through_an_obstacle_stops_at_its_surface() {
+        let center = Vec2::new(10.0, 0.0);
+        // jumps clean over the obstacle in one step
+        let (contact, normal) =
+            obstacle_contact(Vec2::ZERO, Vec2::new(20.0, 0.0), center, 2.0).unwrap();
+        assert!(contact.abs_diff_eq(Vec2::new(8.0, 0.0), 1e-5), "{contact}");
+        assert!(normal.abs_diff_eq(Vec2::NEG_X, 1e-5), "{normal}");
+        assert_eq!(reflect(Vec2::X, normal), Vec2::NEG_X);
+
+        // ending inside is pushed out the nearest way
+        let (contact, normal) =
+            obstacle_contact(Vec2::new(10.0, 5.0), Vec2::new(10.0, 1.0), center, 2.0).unwrap();
+        assert!(contact.abs_diff_eq(Vec2::new(10.0, 2.0), 1e-5), "{contact}");
+        assert!(normal.abs_diff_eq(Vec2::Y, 1e-5), "{normal}");
+
+        assert_eq!(
+            obstacle_contact(Vec2::new(0.0, 5.0), Vec2::new(20.0, 5.0), center, 2.0),
+            None
+        );
+    }
+
+    #[test]
+    fn an_obstacle_crossing_the_path_is_dodged_before_it_arrives() {
+        let position = Vec2::ZERO;
+        let center = Vec2::new(40.0, -40.0);
+        // still beside the path, so a static obstacle there would be ignored
+        let steering = obstacle_avoidance(position, Vec2::X, 50.0, center, 10.0);
+        assert_eq!(steering, Vec2::ZERO);
+
+        // but it is moving up into the path as fast as the boid flies
+        let (direction, lookahead) =
+            relative_lookahead(Vec2::X, Vec2::X * 50.0, 50.0, Vec2::new(0.0, 50.0));
+        let steering = obstacle_avoidance(position, direction, lookahead, center, 10.0);
+        assert!(steering.length() > 0.0);
+
+        assert_eq!(
+            relative_lookahead(Vec2::X, Vec2::X * 50.0, 80.0, Vec2::ZERO),
+            (Vec2::X, 80.0)
+        );
+    }
+
+    #[test]
+    fn moving_obstacles_are_judged_by_the_boids_actual_velocity() {
+        // the obstacle moving up into the path of the previous test, met by a boid cruising at
+        // 50 but actually flying at `speed`
+        let turned = |speed: f32| {
+            let mut app = App::new();
+            app.insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
+                .init_resource::<TimeScale>()
+                .init_resource::<BoidConfig>()
+                .add_systems(Update, avoid_obstacles_system);
+            app.world.spawn((
+                TransformBundle::from_transform(Transform::from_xyz(40.0, -40.0, 0.0)),
+                Obstacle { radius: 10.0 },
+                ObstacleVelocity(Vec2::new(0.0, 50.0)),
+            ));
+            let boid = app
+                .world
+                .spawn((
+                    TransformBundle::default(),
+                    Boid {
+                        direction: Vec2::X,
+                        speed: 50.0,
+                        velocity: Vec2::X * speed,
+                        ..default()
+                    },
+                ))
+                .id();
+            app.update();
+            app.world.get::<Boid>(boid).unwrap().steering != Vec2::ZERO
+        };
+        assert!(turned(50.0));
+        // slowed down, the obstacle is long past by the time the boid gets there
+        assert!(!turned(5.0));
+    }
+
+    #[test]
+    fn avoidance_ramps_up_near_surface() {
+        let far = obstacle_avoidance(Vec2::ZERO, Vec2::X, 50.0, Vec2::new(50.0, 1.0), 10.0);
+        let near = obstacle_avoidance(Vec2::ZERO, Vec2::X, 50.0, Vec2::new(15.0, 1.0), 10.0);
+        assert!(near.length() > far.length());
+    }
+}
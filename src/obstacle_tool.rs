@@ -0,0 +1,107 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{DebugDraw, Obstacle, WorldCursor};
+
+const OBSTACLE_COLOR: Color = Color::ORANGE;
+const PREVIEW_COLOR: Color = Color::rgba(1.0, 0.65, 0.0, 0.4);
+/// World units the placement radius changes per scroll line.
+const RADIUS_STEP: f32 = 5.0;
+const MIN_RADIUS: f32 = 5.0;
+const MAX_RADIUS: f32 = 300.0;
+
+/// While `active`, left click places an `Obstacle` of `radius` at the cursor, right click removes
+/// the nearest one and the scroll wheel resizes the next one.
+///
+/// O toggles it. Add [`obstacle_tool_inactive`] as a run condition to the systems that would
+/// otherwise also react to those clicks and scrolls, like `spawn_boid` and the camera zoom.
+#[derive(Resource)]
+pub struct ObstacleTool {
+    pub active: bool,
+    pub radius: f32,
+}
+
+impl Default for ObstacleTool {
+    fn default() -> Self {
+        Self {
+            active: false,
+            radius: 30.0,
+        }
+    }
+}
+
+pub fn toggle_obstacle_tool_system(mut tool: ResMut<ObstacleTool>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::O) {
+        tool.active = !tool.active;
+    }
+}
+
+/// Run condition that is true unless the obstacle tool is placing obstacles.
+pub fn obstacle_tool_inactive(tool: Option<Res<ObstacleTool>>) -> bool {
+    !tool.is_some_and(|tool| tool.active)
+}
+
+pub fn obstacle_tool_system(
+    mut commands: Commands,
+    mut tool: ResMut<ObstacleTool>,
+    cursor: WorldCursor,
+    buttons: Res<Input<MouseButton>>,
+    mut scroll: EventReader<MouseWheel>,
+    obstacle_query: Query<(Entity, &Transform), With<Obstacle>>,
+) {
+    if !tool.active {
+        scroll.clear();
+        return;
+    }
+    let scrolled: f32 = scroll
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            // roughly one line per 100 pixels on touchpads
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+    if scrolled != 0.0 {
+        tool.radius = (tool.radius + scrolled * RADIUS_STEP).clamp(MIN_RADIUS, MAX_RADIUS);
+    }
+
+    let Some(mouse_pos) = cursor.position() else {
+        return;
+    };
+    if buttons.just_pressed(MouseButton::Left) {
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(mouse_pos.extend(0.0))),
+            Obstacle {
+                radius: tool.radius,
+            },
+        ));
+    }
+    if buttons.just_pressed(MouseButton::Right) {
+        let nearest = obstacle_query.iter().min_by(|(_, a), (_, b)| {
+            let a = a.translation.xy().distance_squared(mouse_pos);
+            let b = b.translation.xy().distance_squared(mouse_pos);
+            a.total_cmp(&b)
+        });
+        if let Some((entity, _)) = nearest {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Outlines every obstacle, and while the tool is active the one a click would place.
+pub fn draw_obstacles_system(
+    tool: Res<ObstacleTool>,
+    cursor: WorldCursor,
+    obstacle_query: Query<(&Transform, &Obstacle)>,
+    mut lines: DebugDraw,
+) {
+    for (transform, obstacle) in obstacle_query.iter() {
+        lines.circle(transform.translation.xy(), obstacle.radius, OBSTACLE_COLOR);
+    }
+    if tool.active {
+        if let Some(mouse_pos) = cursor.position() {
+            lines.circle(mouse_pos, tool.radius, PREVIEW_COLOR);
+        }
+    }
+}
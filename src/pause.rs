@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+
+/// Freezes movement and steering while `paused`, rendering and the camera keep running.
+#[derive(Resource, Default)]
+pub struct SimState {
+    pub paused: bool,
+    /// Run the simulation for a single frame while paused.
+    pub step: bool,
+    /// A recording is playing back, the simulation stays frozen whatever `paused` says.
+    pub playback: bool,
+}
+
+/// Space toggles pause, period advances one frame while paused.
+///
+/// Add it to `PreUpdate` so a step request is seen by this frame's simulation systems
+/// before `clear_step_system` resets it.
+pub fn toggle_pause_system(mut sim_state: ResMut<SimState>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Space) {
+        sim_state.paused = !sim_state.paused;
+    }
+    if sim_state.paused && keys.just_pressed(KeyCode::Period) {
+        sim_state.step = true;
+    }
+}
+
+/// Run condition for every system that moves or steers boids.
+pub fn simulation_running(sim_state: Res<SimState>) -> bool {
+    !sim_state.playback && (!sim_state.paused || sim_state.step)
+}
+
+pub fn clear_step_system(mut sim_state: ResMut<SimState>) {
+    if sim_state.step {
+        sim_state.step = false;
+    }
+}
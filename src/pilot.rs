@@ -0,0 +1,162 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{Boid, WorldCursor};
+
+/// Marks the boids `rotate_boid_manual_system` turns with the arrow keys, any number of them can
+/// be piloted at once.
+///
+/// A piloted boid still flocks, the arrow keys turn it on top of the steering of its neighbors.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PlayerControlled;
+
+/// `Tab` hands control to the boid nearest to the cursor, or with the cursor outside the window
+/// to the boid after the one piloted now, taking it away from every other boid.
+pub fn cycle_player_control_system(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    keys: Res<Input<KeyCode>>,
+    boid_query: Query<(Entity, &Transform, Option<&PlayerControlled>), With<Boid>>,
+) {
+    if !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    let next = match cursor.position() {
+        Some(mouse_pos) => boid_query
+            .iter()
+            .min_by(|(_, a, _), (_, b, _)| {
+                let a = a.translation.xy().distance_squared(mouse_pos);
+                let b = b.translation.xy().distance_squared(mouse_pos);
+                a.total_cmp(&b)
+            })
+            .map(|(entity, ..)| entity),
+        None => {
+            let mut entities: Vec<Entity> = boid_query.iter().map(|(entity, ..)| entity).collect();
+            entities.sort();
+            let current = boid_query
+                .iter()
+                .filter(|(_, _, controlled)| controlled.is_some())
+                .map(|(entity, ..)| entity)
+                .max();
+            current
+                .and_then(|current| entities.iter().find(|entity| **entity > current))
+                .or(entities.first())
+                .copied()
+        }
+    };
+    let Some(next) = next else {
+        return;
+    };
+
+    for (entity, _, controlled) in boid_query.iter() {
+        if controlled.is_some() && entity != next {
+            commands.entity(entity).remove::<PlayerControlled>();
+        }
+    }
+    commands.entity(next).insert(PlayerControlled);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rotate_boid_manual_system, BoidConfig};
+
+    fn pilot_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .init_resource::<BoidConfig>()
+            .add_systems(Update, cycle_player_control_system)
+            .add_systems(
+                Update,
+                rotate_boid_manual_system.after(cycle_player_control_system),
+            );
+        app
+    }
+
+    #[test]
+    fn arrows_only_turn_the_piloted_boid() {
+        let mut app = pilot_app();
+        let mut spawn = || {
+            app.world
+                .spawn((TransformBundle::default(), Boid::default()))
+                .id()
+        };
+        let piloted = spawn();
+        let autonomous = spawn();
+        app.world.entity_mut(piloted).insert(PlayerControlled);
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Left);
+        app.update();
+
+        let steering = |entity| app.world.get::<Boid>(entity).unwrap().steering;
+        // pulled counterclockwise from straight up
+        assert!(steering(piloted).x < 0.0, "{}", steering(piloted));
+        assert_eq!(steering(autonomous), Vec2::ZERO);
+    }
+
+    #[test]
+    fn every_piloted_boid_turns_while_an_arrow_is_held() {
+        let mut app = pilot_app();
+        let piloted: Vec<Entity> = [Vec2::Y, Vec2::X, Vec2::NEG_Y]
+            .into_iter()
+            .map(|direction| {
+                app.world
+                    .spawn((
+                        TransformBundle::default(),
+                        Boid {
+                            direction,
+                            ..default()
+                        },
+                        PlayerControlled,
+                    ))
+                    .id()
+            })
+            .collect();
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Right);
+        app.update();
+
+        for (entity, start) in piloted.into_iter().zip([Vec2::Y, Vec2::X, Vec2::NEG_Y]) {
+            let steering = app.world.get::<Boid>(entity).unwrap().steering;
+            // clockwise, whichever way it was heading
+            assert!(start.perp_dot(steering) < 0.0, "{start} -> {steering}");
+        }
+    }
+
+    #[test]
+    fn tab_without_a_cursor_pilots_the_next_boid() {
+        let mut app = pilot_app();
+        let boids: Vec<Entity> = (0..3)
+            .map(|_| {
+                app.world
+                    .spawn((TransformBundle::default(), Boid::default()))
+                    .id()
+            })
+            .collect();
+        let mut piloted = Vec::new();
+        for _ in 0..4 {
+            let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+            keys.clear();
+            keys.press(KeyCode::Tab);
+            app.update();
+            app.world
+                .resource_mut::<Input<KeyCode>>()
+                .release(KeyCode::Tab);
+            let mut query = app.world.query_filtered::<Entity, With<PlayerControlled>>();
+            piloted.push(query.iter(&app.world).collect::<Vec<_>>());
+        }
+        // one at a time, wrapping around to the first
+        assert_eq!(
+            piloted,
+            [
+                vec![boids[0]],
+                vec![boids[1]],
+                vec![boids[2]],
+                vec![boids[0]]
+            ]
+        );
+    }
+}
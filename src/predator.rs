@@ -0,0 +1,70 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{steer_boid, Boid, BoidConfig, SpatialQuery, WorldCursor};
+
+/// Boids within `threat_radius` of a predator's `Transform` flee from it.
+#[derive(Component)]
+pub struct Predator {
+    pub threat_radius: f32,
+}
+
+impl Default for Predator {
+    fn default() -> Self {
+        Self {
+            threat_radius: 80.0,
+        }
+    }
+}
+
+/// Spawns a predator at the cursor when `P` is released.
+pub fn spawn_predator(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    asset_server: Res<AssetServer>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_released(KeyCode::P) {
+        if let Some(mouse_pos) = cursor.position() {
+            let [x, y] = mouse_pos.to_array();
+            commands.spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(Vec3::splat(2.0)),
+                    texture: asset_server.load("sprites/boid01.png"),
+                    sprite: Sprite {
+                        color: Color::RED,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Predator::default(),
+            ));
+        }
+    }
+}
+
+/// Steers every boid inside a predator's threat radius directly away from it.
+///
+/// Its strength grows toward 1 as the predator gets closer, so its pull on the velocity overrides
+/// that of the flock.
+pub fn boid_flee_system(
+    spatial: SpatialQuery,
+    predator_query: Query<(&Transform, &Predator)>,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+    config: Res<BoidConfig>,
+) {
+    for (predator_transform, predator) in predator_query.iter() {
+        let predator_pos = predator_transform.translation.xy();
+        for (_, entity) in spatial.within_distance(predator_pos, predator.threat_radius) {
+            let Some(entity) = entity else { continue };
+            let Ok((transform, mut boid)) = boid_query.get_mut(entity) else {
+                continue;
+            };
+
+            let away = transform.translation.xy() - predator_pos;
+            let proximity = 1.0 - (away.length() / predator.threat_radius).min(1.0);
+            let weight = boid.rotation_speed * config.flee * proximity;
+            steer_boid(&mut boid, away, weight);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{boid_sprite, Boid, BoidSpriteHandles, FlockFileError, SimState};
+
+/// File written when F7 stops a recording and read when F8 starts playing one back.
+pub const RECORDING_FILE: &str = "recording.ron";
+
+/// Where one boid was and how its sprite was turned in one recorded frame.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RecordedPose {
+    /// The boid's `Entity` as bits, the same for a boid in every frame of a recording.
+    pub id: u64,
+    pub position: Vec2,
+    pub rotation: Quat,
+}
+
+/// What a recorded boid looks like, stored once instead of in every frame.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RecordedBoid {
+    pub species: u32,
+    pub radius: f32,
+}
+
+/// The poses of every boid, frame by frame.
+///
+/// Each frame lists only the boids alive in it, so boids spawned or despawned while recording
+/// appear and disappear at the same frame when played back.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    /// Keyed by `RecordedPose::id`.
+    pub boids: HashMap<u64, RecordedBoid>,
+    pub frames: Vec<Vec<RecordedPose>>,
+}
+
+pub fn save_recording(path: impl AsRef<Path>, recording: &Recording) -> Result<(), FlockFileError> {
+    let ron = ron::ser::to_string(recording).map_err(FlockFileError::Serialize)?;
+    fs::write(path, ron).map_err(FlockFileError::Io)
+}
+
+pub fn load_recording(path: impl AsRef<Path>) -> Result<Recording, FlockFileError> {
+    let ron = fs::read_to_string(path).map_err(FlockFileError::Io)?;
+    ron::from_str(&ron).map_err(FlockFileError::Parse)
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecorderState {
+    #[default]
+    Idle,
+    /// `recorder_system` appends a frame every update.
+    Recording,
+    /// `playback_system` moves stand-in sprites through the frames while the simulation is frozen
+    /// and the live boids are hidden.
+    Playing,
+}
+
+/// The recording being made or played back, F7 and F8 start and stop it.
+#[derive(Resource, Default)]
+pub struct Recorder {
+    pub state: RecorderState,
+    pub recording: Recording,
+    /// Next frame to play.
+    frame: usize,
+    /// Stand-in sprite of every recorded boid shown in the current frame.
+    replayed: HashMap<u64, Entity>,
+}
+
+impl Recorder {
+    /// Starts playing `recording` from its first frame.
+    pub fn play(&mut self, recording: Recording) {
+        self.recording = recording;
+        self.frame = 0;
+        self.state = RecorderState::Playing;
+    }
+}
+
+/// Draws a recorded boid during playback, in place of the hidden `Boid`s.
+#[derive(Component)]
+pub struct Replayed;
+
+/// F7 starts recording, or stops and writes the recording to `RECORDING_FILE`. F8 plays
+/// `RECORDING_FILE` back, or stops the playback.
+pub fn toggle_recording_system(mut recorder: ResMut<Recorder>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::F7) {
+        match recorder.state {
+            RecorderState::Idle => {
+                recorder.recording = Recording::default();
+                recorder.state = RecorderState::Recording;
+                info!("recording");
+            }
+            RecorderState::Recording => {
+                recorder.state = RecorderState::Idle;
+                match save_recording(RECORDING_FILE, &recorder.recording) {
+                    Ok(()) => info!(
+                        "saved {} frames to {RECORDING_FILE}",
+                        recorder.recording.frames.len()
+                    ),
+                    Err(err) => error!("{err}"),
+                }
+            }
+            RecorderState::Playing => {}
+        }
+    }
+    if keys.just_pressed(KeyCode::F8) {
+        match recorder.state {
+            RecorderState::Idle => match load_recording(RECORDING_FILE) {
+                Ok(recording) => recorder.play(recording),
+                Err(err) => error!("{err}"),
+            },
+            RecorderState::Playing => recorder.state = RecorderState::Idle,
+            RecorderState::Recording => {}
+        }
+    }
+}
+
+/// While recording, appends the position and sprite rotation of every boid as one frame.
+///
+/// Add it after `BoidSet::Rendering` so the rotation is the one drawn this frame.
+pub fn recorder_system(
+    mut recorder: ResMut<Recorder>,
+    boid_query: Query<(Entity, &Transform, &Boid)>,
+) {
+    if recorder.state != RecorderState::Recording {
+        return;
+    }
+    let mut frame = Vec::with_capacity(boid_query.iter().len());
+    for (entity, transform, boid) in boid_query.iter() {
+        let id = entity.to_bits();
+        recorder
+            .recording
+            .boids
+            .entry(id)
+            .or_insert_with(|| RecordedBoid {
+                species: boid.species,
+                radius: boid.radius,
+            });
+        frame.push(RecordedPose {
+            id,
+            position: transform.translation.xy(),
+            rotation: transform.rotation,
+        });
+    }
+    recorder.recording.frames.push(frame);
+}
+
+/// Shows one recorded frame per update while playing, then stops at the end of the recording.
+///
+/// Playback sets `SimState::playback`, which stops every simulation system, and hides the live
+/// boids; both are restored once it stops. Recorded boids are drawn by `Replayed` sprites that are
+/// spawned and despawned as the boids come and go in the recording. Without `BoidSpriteHandles`
+/// (e.g. headless) they only get a transform.
+pub fn playback_system(
+    mut commands: Commands,
+    mut recorder: ResMut<Recorder>,
+    mut sim_state: ResMut<SimState>,
+    sprites: Option<Res<BoidSpriteHandles>>,
+    mut boid_visibility: Query<&mut Visibility, With<Boid>>,
+    mut replayed_query: Query<&mut Transform, With<Replayed>>,
+) {
+    let playing = recorder.state == RecorderState::Playing;
+    if playing != sim_state.playback {
+        sim_state.playback = playing;
+        let visibility = if playing {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        for mut boid_visibility in boid_visibility.iter_mut() {
+            *boid_visibility = visibility;
+        }
+        for (_, entity) in recorder.replayed.drain() {
+            commands.entity(entity).despawn();
+        }
+    }
+    if !playing {
+        return;
+    }
+
+    let recorder = &mut *recorder;
+    let Some(frame) = recorder.recording.frames.get(recorder.frame) else {
+        recorder.state = RecorderState::Idle;
+        info!("playback finished");
+        return;
+    };
+    recorder.frame += 1;
+
+    let ids: HashSet<u64> = frame.iter().map(|pose| pose.id).collect();
+    recorder.replayed.retain(|id, entity| {
+        let alive = ids.contains(id);
+        if !alive {
+            commands.entity(*entity).despawn();
+        }
+        alive
+    });
+    for pose in frame {
+        let existing = recorder.replayed.get(&pose.id);
+        if let Some(mut transform) =
+            existing.and_then(|entity| replayed_query.get_mut(*entity).ok())
+        {
+            transform.translation = pose.position.extend(transform.translation.z);
+            transform.rotation = pose.rotation;
+            continue;
+        }
+        let Some(boid) = recorder.recording.boids.get(&pose.id) else {
+            continue;
+        };
+        let entity = match &sprites {
+            Some(sprites) => {
+                let mut sprite = boid_sprite(pose.position, boid.radius, sprites.get(boid.species));
+                sprite.transform.rotation = pose.rotation;
+                commands.spawn((sprite, Replayed)).id()
+            }
+            None => {
+                let transform = Transform::from_translation(pose.position.extend(0.0))
+                    .with_rotation(pose.rotation);
+                commands
+                    .spawn((TransformBundle::from_transform(transform), Replayed))
+                    .id()
+            }
+        };
+        recorder.replayed.insert(pose.id, entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(id: u64, x: f32) -> RecordedPose {
+        RecordedPose {
+            id,
+            position: Vec2::new(x, 0.0),
+            rotation: Quat::from_rotation_z(x),
+        }
+    }
+
+    #[test]
+    fn saved_recording_loads_back_unchanged() {
+        let path = std::env::temp_dir().join("boid_recording_roundtrip.ron");
+        let recording = Recording {
+            boids: HashMap::from_iter([
+                (
+                    1,
+                    RecordedBoid {
+                        species: 0,
+                        radius: 4.0,
+                    },
+                ),
+                (
+                    7,
+                    RecordedBoid {
+                        species: 2,
+                        radius: 6.0,
+                    },
+                ),
+            ]),
+            // boid 7 is spawned in the second frame and boid 1 despawned in the third
+            frames: vec![
+                vec![pose(1, 0.0)],
+                vec![pose(1, 1.0), pose(7, 5.0)],
+                vec![pose(7, 6.0)],
+            ],
+        };
+        save_recording(&path, &recording).unwrap();
+        assert_eq!(load_recording(&path).unwrap(), recording);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn playback_follows_the_recorded_boids_and_then_restores_the_flock() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<SimState>()
+            .init_resource::<Recorder>()
+            .add_systems(Update, recorder_system)
+            .add_systems(Update, playback_system);
+        let first = app
+            .world
+            .spawn((SpatialBundle::default(), Boid::default()))
+            .id();
+        app.world.resource_mut::<Recorder>().state = RecorderState::Recording;
+        app.update();
+        app.world.get_mut::<Transform>(first).unwrap().translation.x = 10.0;
+        let second = app
+            .world
+            .spawn((SpatialBundle::default(), Boid::default()))
+            .id();
+        app.update();
+        app.world.entity_mut(first).despawn();
+        app.update();
+
+        let replayed_positions = |app: &mut App| {
+            let mut positions: Vec<f32> = app
+                .world
+                .query_filtered::<&Transform, With<Replayed>>()
+                .iter(&app.world)
+                .map(|transform| transform.translation.x)
+                .collect();
+            positions.sort_by(f32::total_cmp);
+            positions
+        };
+        let mut recorder = app.world.resource_mut::<Recorder>();
+        recorder.state = RecorderState::Idle;
+        let recording = std::mem::take(&mut recorder.recording);
+        assert_eq!(recording.frames.len(), 3);
+        recorder.play(recording);
+
+        app.update();
+        assert!(app.world.resource::<SimState>().playback);
+        assert_eq!(
+            app.world.get::<Visibility>(second),
+            Some(&Visibility::Hidden)
+        );
+        assert_eq!(replayed_positions(&mut app), [0.0]);
+        app.update();
+        assert_eq!(replayed_positions(&mut app), [0.0, 10.0]);
+        app.update();
+        assert_eq!(replayed_positions(&mut app), [0.0]);
+
+        // past the last frame the live flock comes back
+        app.update();
+        app.update();
+        assert!(!app.world.resource::<SimState>().playback);
+        assert!(replayed_positions(&mut app).is_empty());
+        assert_eq!(
+            app.world.get::<Visibility>(second),
+            Some(&Visibility::Inherited)
+        );
+    }
+}
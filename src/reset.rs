@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use crate::{Boid, NNTree, NeighborCache, Obstacle, Predator, ScatterTimer, UniformGrid};
+
+/// Predators and obstacles, only removed by a reset with Shift held.
+type HazardFilter = Or<(With<Predator>, With<Obstacle>)>;
+
+/// Delete removes every boid and resets the state kept about the flock, holding Shift also
+/// removes predators and obstacles.
+///
+/// The spatial structures are emptied right away rather than at the next rebuild, which is skipped
+/// while paused, so no query sees the removed boids.
+#[allow(clippy::too_many_arguments)]
+pub fn reset_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    boid_query: Query<Entity, With<Boid>>,
+    hazard_query: Query<Entity, HazardFilter>,
+    mut tree: ResMut<NNTree>,
+    mut grid: ResMut<UniformGrid>,
+    mut neighbor_cache: ResMut<NeighborCache>,
+    mut scatter: ResMut<ScatterTimer>,
+) {
+    if !keys.just_pressed(KeyCode::Delete) {
+        return;
+    }
+    let mut removed = 0;
+    for entity in boid_query.iter() {
+        commands.entity(entity).despawn_recursive();
+        removed += 1;
+    }
+    *tree = NNTree::default();
+    *grid = UniformGrid::default();
+    *neighbor_cache = NeighborCache::default();
+    *scatter = ScatterTimer::default();
+    info!("reset removed {removed} boids");
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        for entity in hazard_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        info!(
+            "reset removed {} predators and obstacles",
+            hazard_query.iter().len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset(shift: bool) -> App {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .init_resource::<NNTree>()
+            .init_resource::<UniformGrid>()
+            .init_resource::<NeighborCache>()
+            .init_resource::<ScatterTimer>()
+            .add_systems(Update, reset_system);
+        app.world.spawn(Boid::default());
+        app.world.spawn(Boid::default());
+        app.world.spawn(Predator::default());
+        app.world.spawn(Obstacle { radius: 10.0 });
+        app.world.spawn(Camera2dBundle::default());
+        let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+        keys.press(KeyCode::Delete);
+        if shift {
+            keys.press(KeyCode::ShiftLeft);
+        }
+        app.update();
+        app
+    }
+
+    #[test]
+    fn reset_keeps_predators_and_obstacles_without_shift() {
+        let mut app = reset(false);
+        assert_eq!(app.world.query::<&Boid>().iter(&app.world).len(), 0);
+        assert_eq!(app.world.query::<&Predator>().iter(&app.world).len(), 1);
+        assert_eq!(app.world.query::<&Obstacle>().iter(&app.world).len(), 1);
+        assert_eq!(app.world.query::<&Camera>().iter(&app.world).len(), 1);
+
+        let mut app = reset(true);
+        assert_eq!(app.world.query::<&Predator>().iter(&app.world).len(), 0);
+        assert_eq!(app.world.query::<&Obstacle>().iter(&app.world).len(), 0);
+        assert_eq!(app.world.query::<&Camera>().iter(&app.world).len(), 1);
+    }
+}
@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Environment variable read for the seed when no `RngResource` is inserted up front.
+pub const SEED_ENV_VAR: &str = "BOID_SEED";
+
+/// The single source of randomness for spawning, so a run can be reproduced from its seed.
+///
+/// Insert `RngResource::seeded` before `BoidPlugin` to pick the seed in code, otherwise it is read
+/// from `BOID_SEED` or picked at random and logged.
+///
+/// Systems drawing from it loop over the boids serially, a shared rng in a parallel loop would
+/// make the draws depend on thread timing and the run could no longer be reproduced.
+#[derive(Resource, Deref, DerefMut)]
+pub struct RngResource(pub StdRng);
+
+impl RngResource {
+    pub fn seeded(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for RngResource {
+    fn default() -> Self {
+        let seed = match std::env::var(SEED_ENV_VAR) {
+            Ok(seed) => seed.parse().unwrap_or_else(|_| {
+                warn!("{SEED_ENV_VAR}={seed} is not a valid u64, picking a random seed");
+                rand::random()
+            }),
+            Err(_) => rand::random(),
+        };
+        info!("seeding boid rng with {seed}");
+        Self::seeded(seed)
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    current_speed, get_random_direction, rotate_vector, Boid, RngResource, SimulationStep,
+};
+
+/// Seconds cohesion stays off after a scatter, long enough for the flock to spread out.
+const SCATTER_DURATION: f32 = 2.0;
+/// Largest angle, in radians, a scattered boid deviates from straight away from the centroid.
+const SCATTER_SPREAD: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Suppresses cohesion while running, started by `scatter_system`.
+#[derive(Resource, Deref, DerefMut)]
+pub struct ScatterTimer(pub Timer);
+
+impl Default for ScatterTimer {
+    fn default() -> Self {
+        // finished, so the flock starts out cohesive
+        let mut timer = Timer::from_seconds(SCATTER_DURATION, TimerMode::Once);
+        timer.tick(Duration::from_secs_f32(SCATTER_DURATION));
+        Self(timer)
+    }
+}
+
+impl ScatterTimer {
+    pub fn active(&self) -> bool {
+        !self.0.finished()
+    }
+}
+
+/// Advances the scatter timer by one fixed step, so pausing also pauses the scatter.
+pub fn tick_scatter_timer_system(mut scatter: ResMut<ScatterTimer>, time: SimulationStep) {
+    scatter.tick(time.delta());
+}
+
+/// X sends every boid flying away from the flock's centroid and turns cohesion off for a while.
+pub fn scatter_system(
+    keys: Res<Input<KeyCode>>,
+    mut scatter: ResMut<ScatterTimer>,
+    mut rng: ResMut<RngResource>,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+) {
+    if !keys.just_pressed(KeyCode::X) {
+        return;
+    }
+    let count = boid_query.iter().len();
+    if count == 0 {
+        return;
+    }
+    let centroid = boid_query
+        .iter()
+        .map(|(transform, _)| transform.translation.xy())
+        .sum::<Vec2>()
+        / count as f32;
+
+    for (transform, mut boid) in boid_query.iter_mut() {
+        let direction = scatter_direction(transform.translation.xy(), centroid, &mut rng.0);
+        let speed = current_speed(&boid);
+        boid.direction = direction;
+        // set the velocity too, otherwise the turn rate limit would spread the turn over many steps
+        boid.velocity = direction * speed;
+    }
+    scatter.reset();
+}
+
+/// Away from `centroid`, randomly turned by up to `SCATTER_SPREAD`. A boid at the centroid picks
+/// any direction.
+fn scatter_direction(position: Vec2, centroid: Vec2, rng: &mut impl Rng) -> Vec2 {
+    match (position - centroid).try_normalize() {
+        Some(away) => rotate_vector(away, rng.gen_range(-SCATTER_SPREAD..=SCATTER_SPREAD)),
+        None => get_random_direction(rng),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scatter_randomizes_directions_away_from_the_centroid() {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .init_resource::<ScatterTimer>()
+            .insert_resource(RngResource::seeded(0))
+            .add_systems(Update, scatter_system);
+        let positions = [
+            Vec2::new(10.0, 0.0),
+            Vec2::new(-10.0, 0.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::new(0.0, -10.0),
+            Vec2::new(7.0, 7.0),
+        ];
+        for position in positions {
+            app.world.spawn((
+                Transform::from_translation(position.extend(0.0)),
+                Boid {
+                    direction: Vec2::Y,
+                    ..default()
+                },
+            ));
+        }
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::X);
+        app.update();
+
+        let centroid = positions.iter().sum::<Vec2>() / positions.len() as f32;
+        let mut directions = Vec::new();
+        for (transform, boid) in app.world.query::<(&Transform, &Boid)>().iter(&app.world) {
+            let away = transform.translation.xy() - centroid;
+            assert!(boid.direction.dot(away) > 0.0, "{} {away}", boid.direction);
+            assert!(boid.velocity.normalize().abs_diff_eq(boid.direction, 1e-5));
+            directions.push(boid.direction);
+        }
+        assert!(directions.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(app.world.resource::<ScatterTimer>().active());
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{pair_separation_distance, Boid, DebugDraw, SpatialQuery, WorldCursor};
+
+/// How close to the cursor a Ctrl+click has to be to select a boid.
+const SELECT_RADIUS: f32 = 15.0;
+const VIEW_COLOR: Color = Color::YELLOW;
+const SEPARATION_COLOR: Color = Color::RED;
+const DIRECTION_COLOR: Color = Color::WHITE;
+
+/// Marks the boid picked with `select_boid_system`.
+#[derive(Component)]
+pub struct Selected;
+
+const SELECT_KEYS: [KeyCode; 2] = [KeyCode::ControlLeft, KeyCode::ControlRight];
+
+/// Run condition that is true unless Ctrl is held, so a selecting click doesn't also spawn a boid.
+pub fn not_selecting(keys: Res<Input<KeyCode>>) -> bool {
+    !keys.any_pressed(SELECT_KEYS)
+}
+
+/// Ctrl+left click selects the boid nearest to the cursor and logs its fields, clicking empty
+/// space clears the selection.
+pub fn select_boid_system(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    spatial: SpatialQuery,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    selected_query: Query<Entity, With<Selected>>,
+    boid_query: Query<&Boid>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) || !keys.any_pressed(SELECT_KEYS) {
+        return;
+    }
+    let Some(mouse_pos) = cursor.position() else {
+        return;
+    };
+    for entity in selected_query.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+    let Some((_, Some(entity))) = spatial.nearest_within(mouse_pos, SELECT_RADIUS) else {
+        return;
+    };
+    if let Ok(boid) = boid_query.get(entity) {
+        commands.entity(entity).insert(Selected);
+        info!("selected {entity:?}: {boid:?}");
+    }
+}
+
+/// Draws the selected boid's view and separation circles and its heading. The lines to its
+/// neighbors come from `neighbor_links_system`.
+pub fn selection_overlay_system(
+    selected_query: Query<(&Transform, &Boid), With<Selected>>,
+    mut lines: DebugDraw,
+) {
+    for (transform, boid) in selected_query.iter() {
+        let position = transform.translation.xy();
+        lines.circle(position, boid.view_distance, VIEW_COLOR);
+        lines.circle(
+            position,
+            // to a neighbor of the same size
+            pair_separation_distance(boid, boid.radius),
+            SEPARATION_COLOR,
+        );
+        lines.line_colored(
+            position.extend(0.0),
+            (position + boid.direction * boid.view_distance).extend(0.0),
+            DIRECTION_COLOR,
+        );
+    }
+}
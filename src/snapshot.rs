@@ -0,0 +1,237 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{boid_sprite, Boid, BoidSpriteHandles, MaxBoids, BOID_SPRITE_RADIUS};
+
+/// File written by F5 and read by F9, relative to the working directory.
+pub const FLOCK_FILE: &str = "flock.ron";
+
+/// Everything needed to respawn a boid exactly where and how it was saved.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BoidSnapshot {
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub view_distance: f32,
+    pub separation_distance: f32,
+    /// Missing in files saved before boids had a size.
+    #[serde(default = "default_radius")]
+    pub radius: f32,
+    /// The fields below are missing in files saved before they were kept, those boids come back
+    /// with the defaults and their velocity seeded from `speed`.
+    #[serde(default)]
+    pub species: u32,
+    #[serde(default)]
+    pub velocity: Vec2,
+    #[serde(default = "default_min_speed")]
+    pub min_speed: f32,
+    #[serde(default = "default_max_speed")]
+    pub max_speed: f32,
+    #[serde(default = "default_fov_degrees")]
+    pub fov_degrees: f32,
+}
+
+fn default_radius() -> f32 {
+    BOID_SPRITE_RADIUS
+}
+
+fn default_min_speed() -> f32 {
+    Boid::default().min_speed
+}
+
+fn default_max_speed() -> f32 {
+    Boid::default().max_speed
+}
+
+fn default_fov_degrees() -> f32 {
+    Boid::default().fov_degrees
+}
+
+impl BoidSnapshot {
+    pub fn new(position: Vec2, boid: &Boid) -> Self {
+        Self {
+            position,
+            direction: boid.direction,
+            speed: boid.speed,
+            rotation_speed: boid.rotation_speed,
+            view_distance: boid.view_distance,
+            separation_distance: boid.separation_distance,
+            radius: boid.radius,
+            species: boid.species,
+            velocity: boid.velocity,
+            min_speed: boid.min_speed,
+            max_speed: boid.max_speed,
+            fov_degrees: boid.fov_degrees,
+        }
+    }
+
+    pub fn to_boid(&self) -> Boid {
+        Boid {
+            speed: self.speed,
+            rotation_speed: self.rotation_speed,
+            direction: self.direction,
+            view_distance: self.view_distance,
+            separation_distance: self.separation_distance,
+            radius: self.radius,
+            species: self.species,
+            velocity: self.velocity,
+            min_speed: self.min_speed,
+            max_speed: self.max_speed,
+            fov_degrees: self.fov_degrees,
+            ..default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FlockFileError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for FlockFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access flock file: {err}"),
+            Self::Serialize(err) => write!(f, "could not serialize flock: {err}"),
+            Self::Parse(err) => write!(f, "malformed flock file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for FlockFileError {}
+
+pub fn save_flock(path: impl AsRef<Path>, flock: &[BoidSnapshot]) -> Result<(), FlockFileError> {
+    let ron = ron::ser::to_string_pretty(flock, ron::ser::PrettyConfig::default())
+        .map_err(FlockFileError::Serialize)?;
+    fs::write(path, ron).map_err(FlockFileError::Io)
+}
+
+pub fn load_flock(path: impl AsRef<Path>) -> Result<Vec<BoidSnapshot>, FlockFileError> {
+    let ron = fs::read_to_string(path).map_err(FlockFileError::Io)?;
+    ron::from_str(&ron).map_err(FlockFileError::Parse)
+}
+
+/// F5 writes every boid to `FLOCK_FILE`.
+pub fn save_flock_system(keys: Res<Input<KeyCode>>, boid_query: Query<(&Transform, &Boid)>) {
+    if !keys.just_pressed(KeyCode::F5) {
+        return;
+    }
+    let flock: Vec<BoidSnapshot> = boid_query
+        .iter()
+        .map(|(transform, boid)| BoidSnapshot::new(transform.translation.xy(), boid))
+        .collect();
+    match save_flock(FLOCK_FILE, &flock) {
+        Ok(()) => info!("saved {} boids to {FLOCK_FILE}", flock.len()),
+        Err(err) => error!("{err}"),
+    }
+}
+
+/// F9 replaces every boid with the ones saved in `FLOCK_FILE`.
+///
+/// If the file is missing or malformed the current flock is kept. Boids past `MaxBoids` are dropped.
+pub fn load_flock_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    sprites: Res<BoidSpriteHandles>,
+    boid_query: Query<Entity, With<Boid>>,
+    max_boids: Res<MaxBoids>,
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let mut flock = match load_flock(FLOCK_FILE) {
+        Ok(flock) => flock,
+        Err(err) => {
+            error!("{err}");
+            return;
+        }
+    };
+
+    if flock.len() > max_boids.0 {
+        warn!(
+            "{FLOCK_FILE} holds {} boids, only loading {}",
+            flock.len(),
+            max_boids.0
+        );
+        flock.truncate(max_boids.0);
+    }
+
+    for entity in boid_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for snapshot in &flock {
+        let boid = snapshot.to_boid();
+        commands.spawn((
+            boid_sprite(
+                snapshot.position,
+                snapshot.radius,
+                sprites.get(boid.species),
+            ),
+            boid,
+        ));
+    }
+    info!("loaded {} boids from {FLOCK_FILE}", flock.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_flock_loads_back_unchanged() {
+        let path = std::env::temp_dir().join("boid_snapshot_roundtrip.ron");
+        let flock = vec![
+            BoidSnapshot::new(Vec2::new(10.0, 20.0), &Boid::default()),
+            BoidSnapshot {
+                position: Vec2::new(-5.0, 300.0),
+                direction: Vec2::X,
+                speed: 33.0,
+                rotation_speed: 1.5,
+                view_distance: 80.0,
+                separation_distance: 12.0,
+                radius: 9.0,
+                species: 2,
+                velocity: Vec2::new(27.0, 4.0),
+                min_speed: 15.0,
+                max_speed: 55.0,
+                fov_degrees: 270.0,
+            },
+        ];
+        save_flock(&path, &flock).unwrap();
+        assert_eq!(load_flock(&path).unwrap(), flock);
+        // and the boids respawned from them save back the same
+        let position = flock[1].position;
+        assert_eq!(BoidSnapshot::new(position, &flock[1].to_boid()), flock[1]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn old_flock_files_load_with_defaults() {
+        let snapshot: Vec<BoidSnapshot> = ron::from_str(
+            "[(position: (1.0, 2.0), direction: (0.0, 1.0), speed: 20.0, rotation_speed: 3.0, \
+             view_distance: 50.0, separation_distance: 2.0)]",
+        )
+        .unwrap();
+        let position = Vec2::new(1.0, 2.0);
+        assert_eq!(
+            BoidSnapshot::new(position, &snapshot[0].to_boid()),
+            BoidSnapshot::new(position, &Boid::default())
+        );
+    }
+
+    #[test]
+    fn malformed_flock_file_is_an_error() {
+        let path = std::env::temp_dir().join("boid_snapshot_malformed.ron");
+        fs::write(&path, "[(position: oops)]").unwrap();
+        assert!(matches!(load_flock(&path), Err(FlockFileError::Parse(_))));
+        fs::remove_file(path).unwrap();
+    }
+}
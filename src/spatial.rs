@@ -0,0 +1,370 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_spatial::point::Point2;
+use bevy_spatial::SpatialAccess;
+use kd_tree::KdTree;
+
+use crate::{Boid, NNTree, WorldBounds};
+
+/// Which structure answers neighbor queries.
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Default)]
+pub enum SpatialBackend {
+    /// The `bevy_spatial` KD-tree rebuilt by `rebuild_tree_system`.
+    #[default]
+    KdTree,
+    /// A uniform grid of square cells, faster for large flocks of even density.
+    /// A `cell_size` close to the boids' `view_distance` works best, smaller than
+    /// `MIN_GRID_CELL_SIZE` is raised to it.
+    Grid { cell_size: f32 },
+}
+
+/// Smallest cell the grid backend uses. A query walks every cell its distance overlaps, so a cell
+/// of zero or close to it would never finish.
+pub const MIN_GRID_CELL_SIZE: f32 = 1.0;
+
+/// How often `rebuild_tree_system` rebuilds the KD-tree, with a way to rebuild it right away.
+///
+/// Between rebuilds every neighbor query sees the positions of the last one, so a long `interval`
+/// saves work in big flocks at the cost of boids reacting to where their neighbors were, and not
+/// seeing boids spawned since at all. The default rebuilds once per frame. Insert it before
+/// `BoidPlugin` to start with a different interval, it can be changed at any time after.
+///
+/// The grid backend is rebuilt every frame regardless.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SpatialRefresh {
+    pub interval: Duration,
+    rebuild: bool,
+}
+
+impl Default for SpatialRefresh {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(1),
+            rebuild: false,
+        }
+    }
+}
+
+impl SpatialRefresh {
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            interval,
+            ..default()
+        }
+    }
+
+    /// Rebuilds the tree before the neighbor cache is filled next frame, even while paused, so the
+    /// queries reflect e.g. a mass spawn right away instead of at the next scheduled rebuild.
+    pub fn rebuild(&mut self) {
+        self.rebuild = true;
+    }
+}
+
+/// Rebuilds the KD-tree from every boid's position each `SpatialRefresh::interval`.
+///
+/// `bevy_spatial`'s own `AutomaticUpdate` can only rebuild in `Update`, after the fixed steps of
+/// the frame have already read the tree, so `BoidPlugin` runs this in `PreUpdate` instead.
+pub fn rebuild_tree_system(
+    refresh: Res<SpatialRefresh>,
+    mut tree: ResMut<NNTree>,
+    boid_query: Query<(Entity, &Transform), With<Boid>>,
+    time: Res<Time>,
+    mut timer: Local<Timer>,
+) {
+    if timer.duration() != refresh.interval {
+        timer.set_mode(TimerMode::Repeating);
+        timer.set_duration(refresh.interval);
+    }
+    timer.tick(time.delta());
+    if timer.just_finished() {
+        tree.tree = KdTree::build_by_ordered_float(tree_points(&boid_query));
+    }
+}
+
+/// Rebuilds the KD-tree from every boid's position when `SpatialRefresh::rebuild` was called.
+pub fn forced_rebuild_system(
+    mut refresh: ResMut<SpatialRefresh>,
+    mut tree: ResMut<NNTree>,
+    boid_query: Query<(Entity, &Transform), With<Boid>>,
+) {
+    if !refresh.rebuild {
+        return;
+    }
+    tree.tree = KdTree::build_by_ordered_float(tree_points(&boid_query));
+    refresh.rebuild = false;
+}
+
+fn tree_points(boid_query: &Query<(Entity, &Transform), With<Boid>>) -> Vec<Point2> {
+    boid_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.xy()).into())
+        .collect()
+}
+
+/// Boid positions bucketed into square cells, only kept up to date with `SpatialBackend::Grid`.
+#[derive(Resource, Default)]
+pub struct UniformGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Vec2, Entity)>>,
+}
+
+impl UniformGrid {
+    pub fn rebuild(&mut self, cell_size: f32, points: impl Iterator<Item = (Vec2, Entity)>) {
+        self.cell_size = cell_size;
+        self.cells.clear();
+        for (position, entity) in points {
+            self.cells
+                .entry(self.cell(position))
+                .or_default()
+                .push((position, entity));
+        }
+    }
+
+    /// All points within `distance` of `loc`, in the same shape `SpatialAccess` returns them.
+    pub fn within_distance(&self, loc: Vec2, distance: f32) -> Vec<(Vec2, Option<Entity>)> {
+        if self.cells.is_empty() {
+            return vec![];
+        }
+        let min = self.cell(loc - Vec2::splat(distance));
+        let max = self.cell(loc + Vec2::splat(distance));
+        let mut result = vec![];
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let Some(cell) = self.cells.get(&IVec2::new(x, y)) else {
+                    continue;
+                };
+                result.extend(
+                    cell.iter()
+                        .filter(|(position, _)| position.distance(loc) <= distance)
+                        .map(|(position, entity)| (*position, Some(*entity))),
+                );
+            }
+        }
+        result
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+}
+
+pub fn update_grid_system(
+    backend: Res<SpatialBackend>,
+    mut grid: ResMut<UniformGrid>,
+    boid_query: Query<(&Transform, Entity), With<Boid>>,
+) {
+    if let SpatialBackend::Grid { cell_size } = *backend {
+        // max also turns a NaN size into the minimum
+        grid.rebuild(
+            cell_size.max(MIN_GRID_CELL_SIZE),
+            boid_query
+                .iter()
+                .map(|(transform, entity)| (transform.translation.xy(), entity)),
+        );
+    }
+}
+
+/// Run condition for the KD-tree rebuild, skipped while the grid backend is active.
+pub fn kdtree_backend(backend: Res<SpatialBackend>) -> bool {
+    *backend == SpatialBackend::KdTree
+}
+
+/// Neighbor queries dispatched to whichever `SpatialBackend` is active.
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w> {
+    backend: Res<'w, SpatialBackend>,
+    tree: Res<'w, NNTree>,
+    grid: Res<'w, UniformGrid>,
+}
+
+impl<'w> SpatialQuery<'w> {
+    pub fn within_distance(&self, loc: Vec2, distance: f32) -> Vec<(Vec2, Option<Entity>)> {
+        match *self.backend {
+            SpatialBackend::KdTree => self.tree.within_distance(loc, distance),
+            SpatialBackend::Grid { .. } => self.grid.within_distance(loc, distance),
+        }
+    }
+
+    /// Like `within_distance`, but with `bounds` joined up at its opposite edges along the axes
+    /// that `wrap`, for `BoundaryMode::Wrap`.
+    pub fn within_distance_wrapped(
+        &self,
+        loc: Vec2,
+        distance: f32,
+        bounds: &WorldBounds,
+        wrap: BVec2,
+    ) -> Vec<(Vec2, Option<Entity>)> {
+        wrapped_within_distance(loc, distance, bounds, wrap, |loc, distance| {
+            self.within_distance(loc, distance)
+        })
+    }
+
+    /// Closest point within `distance` of `loc`.
+    pub fn nearest_within(&self, loc: Vec2, distance: f32) -> Option<(Vec2, Option<Entity>)> {
+        self.within_distance(loc, distance)
+            .into_iter()
+            .min_by(|(a, _), (b, _)| a.distance_squared(loc).total_cmp(&b.distance_squared(loc)))
+    }
+}
+
+/// Runs `query` at `loc` and, when the circle crosses an edge of `bounds` along an axis that
+/// wraps, again at `loc` moved by the world size toward the opposite edge.
+///
+/// Points found across the seam are moved back by the same amount, so they are returned where
+/// they appear to the boid at `loc` and offsets to them point across the edge. A point reachable
+/// through several seams is only kept at its nearest copy.
+fn wrapped_within_distance(
+    loc: Vec2,
+    distance: f32,
+    bounds: &WorldBounds,
+    wrap: BVec2,
+    query: impl Fn(Vec2, f32) -> Vec<(Vec2, Option<Entity>)>,
+) -> Vec<(Vec2, Option<Entity>)> {
+    let size = bounds.max - bounds.min;
+    let shifts = |wrap: bool, loc: f32, min: f32, max: f32, size: f32| {
+        let mut shifts = vec![0.0];
+        if wrap && loc - distance < min {
+            shifts.push(size);
+        }
+        if wrap && loc + distance > max {
+            shifts.push(-size);
+        }
+        shifts
+    };
+    let x_shifts = shifts(wrap.x, loc.x, bounds.min.x, bounds.max.x, size.x);
+    let y_shifts = shifts(wrap.y, loc.y, bounds.min.y, bounds.max.y, size.y);
+    if x_shifts.len() == 1 && y_shifts.len() == 1 {
+        return query(loc, distance);
+    }
+
+    let mut nearest: HashMap<Option<Entity>, Vec2> = HashMap::new();
+    let mut anonymous = vec![];
+    for &x in &x_shifts {
+        for &y in &y_shifts {
+            let shift = Vec2::new(x, y);
+            for (position, entity) in query(loc + shift, distance) {
+                let position = position - shift;
+                if entity.is_none() {
+                    anonymous.push((position, entity));
+                    continue;
+                }
+                let kept = nearest.entry(entity).or_insert(position);
+                if position.distance_squared(loc) < kept.distance_squared(loc) {
+                    *kept = position;
+                }
+            }
+        }
+    }
+    anonymous.extend(
+        nearest
+            .into_iter()
+            .map(|(entity, position)| (position, entity)),
+    );
+    anonymous
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_within_distance_matches_brute_force() {
+        let points: Vec<(Vec2, Entity)> = (0..200)
+            .map(|i| {
+                let i = i as f32;
+                (
+                    Vec2::new((i * 37.0) % 301.0, (i * 91.0) % 257.0),
+                    Entity::from_raw(i as u32),
+                )
+            })
+            .collect();
+        let mut grid = UniformGrid::default();
+        grid.rebuild(25.0, points.iter().copied());
+
+        let loc = Vec2::new(140.0, 120.0);
+        let mut found: Vec<u32> = grid
+            .within_distance(loc, 60.0)
+            .iter()
+            .map(|(_, entity)| entity.unwrap().index())
+            .collect();
+        found.sort();
+        let expected: Vec<u32> = points
+            .iter()
+            .filter(|(position, _)| position.distance(loc) <= 60.0)
+            .map(|(_, entity)| entity.index())
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn grid_cell_size_is_raised_to_the_minimum() {
+        let mut app = App::new();
+        app.insert_resource(SpatialBackend::Grid { cell_size: 0.0 })
+            .init_resource::<UniformGrid>()
+            .add_systems(Update, update_grid_system);
+        let boid = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(10.0, 10.0, 0.0)),
+                Boid::default(),
+            ))
+            .id();
+        app.update();
+
+        let grid = app.world.resource::<UniformGrid>();
+        assert_eq!(grid.cell_size, MIN_GRID_CELL_SIZE);
+        assert_eq!(
+            grid.within_distance(Vec2::new(12.0, 10.0), 5.0),
+            [(Vec2::new(10.0, 10.0), Some(boid))]
+        );
+    }
+
+    #[test]
+    fn boids_on_opposite_edges_are_wrapped_neighbors() {
+        let bounds = WorldBounds {
+            min: Vec2::ZERO,
+            max: Vec2::new(400.0, 300.0),
+        };
+        let left = Entity::from_raw(0);
+        let right = Entity::from_raw(1);
+        let mut grid = UniformGrid::default();
+        grid.rebuild(
+            50.0,
+            [
+                (Vec2::new(3.0, 150.0), left),
+                (Vec2::new(395.0, 150.0), right),
+            ]
+            .into_iter(),
+        );
+        let query = |loc, distance| grid.within_distance(loc, distance);
+
+        let flat: Vec<_> = query(Vec2::new(395.0, 150.0), 20.0);
+        assert_eq!(flat, vec![(Vec2::new(395.0, 150.0), Some(right))]);
+
+        let mut wrapped =
+            wrapped_within_distance(Vec2::new(395.0, 150.0), 20.0, &bounds, BVec2::TRUE, query);
+        wrapped.sort_by_key(|(_, entity)| entity.unwrap().index());
+        // the left boid shows up just past the right edge, 8 units away
+        assert_eq!(
+            wrapped,
+            vec![
+                (Vec2::new(403.0, 150.0), Some(left)),
+                (Vec2::new(395.0, 150.0), Some(right)),
+            ]
+        );
+
+        // a wall between them instead of a seam
+        let walled = wrapped_within_distance(
+            Vec2::new(395.0, 150.0),
+            20.0,
+            &bounds,
+            BVec2::new(false, true),
+            query,
+        );
+        assert_eq!(walled, flat);
+    }
+}
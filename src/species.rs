@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::flocking::SteeringWeights;
+use crate::{Boid, BoidConfig};
+
+/// Flocking weights and radii shared by every boid of one species.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeciesProfile {
+    pub cohesion: f32,
+    pub alignment: f32,
+    pub separation: f32,
+    pub view_distance: f32,
+    pub separation_distance: f32,
+}
+
+/// Per-species profiles, keyed by `Boid::species`.
+///
+/// A species without a profile flocks with the `BoidConfig` weights and keeps the radii it was
+/// spawned with, so the empty default behaves like a single shared profile.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SpeciesConfig {
+    pub profiles: HashMap<u32, SpeciesProfile>,
+}
+
+impl SpeciesConfig {
+    pub fn with_profile(mut self, species: u32, profile: SpeciesProfile) -> Self {
+        self.profiles.insert(species, profile);
+        self
+    }
+
+    /// The cohesion, alignment and separation weights of `species`, those of `config` if it has
+    /// no profile.
+    pub fn weights(&self, species: u32, config: &BoidConfig) -> SteeringWeights {
+        match self.profiles.get(&species) {
+            Some(profile) => SteeringWeights {
+                cohesion: profile.cohesion,
+                alignment: profile.alignment,
+                separation: profile.separation,
+            },
+            None => config.steering_weights(),
+        }
+    }
+}
+
+/// How strongly boids of one species separate from neighbors of another, keyed by
+/// `(own species, neighbor species)`, like the weights in `BoidConfig`.
+///
+/// Pairs need not be symmetric: red can keep clear of blue while blue flies straight through red.
+/// A zero ignores the neighbor altogether. Pairs without an entry repel at full strength within
+/// a species, and across species only with `BoidConfig::separate_across_species`.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SeparationMatrix {
+    pub strengths: HashMap<(u32, u32), f32>,
+}
+
+impl SeparationMatrix {
+    pub fn with_strength(mut self, species: u32, neighbor_species: u32, strength: f32) -> Self {
+        self.strengths.insert((species, neighbor_species), strength);
+        self
+    }
+
+    /// How strongly a boid of `species` is pushed away from a neighbor of `neighbor_species`.
+    pub fn strength(&self, species: u32, neighbor_species: u32, config: &BoidConfig) -> f32 {
+        match self.strengths.get(&(species, neighbor_species)) {
+            Some(strength) => *strength,
+            None if species == neighbor_species || config.separate_across_species => 1.0,
+            None => 0.0,
+        }
+    }
+}
+
+/// Gives every boid the view and separation distance of its species' profile.
+///
+/// Runs before the neighbor cache is filled so the new radii apply from the same frame. Overrides
+/// radii set on individual boids, e.g. by the inspector, for species that have a profile.
+pub fn apply_species_radii_system(species: Res<SpeciesConfig>, mut boid_query: Query<&mut Boid>) {
+    if species.profiles.is_empty() {
+        return;
+    }
+    for mut boid in boid_query.iter_mut() {
+        let Some(profile) = species.profiles.get(&boid.species) else {
+            continue;
+        };
+        // only write on a difference so change detection stays meaningful
+        if boid.view_distance != profile.view_distance
+            || boid.separation_distance != profile.separation_distance
+        {
+            boid.view_distance = profile.view_distance;
+            boid.separation_distance = profile.separation_distance;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn species_without_a_profile_use_the_global_weights() {
+        let config = BoidConfig::default();
+        let species = SpeciesConfig::default().with_profile(
+            1,
+            SpeciesProfile {
+                cohesion: 1.5,
+                alignment: 0.1,
+                separation: 0.0,
+                view_distance: 80.0,
+                separation_distance: 5.0,
+            },
+        );
+
+        let unprofiled = species.weights(0, &config);
+        assert_eq!(unprofiled.cohesion, config.cohesion);
+        assert_eq!(unprofiled.separation, config.separation);
+
+        let profiled = species.weights(1, &config);
+        assert_eq!(profiled.cohesion, 1.5);
+        assert_eq!(profiled.separation, 0.0);
+    }
+}
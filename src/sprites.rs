@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Image every boid is drawn with unless `BoidSprites` says otherwise.
+pub const DEFAULT_BOID_SPRITE: &str = "sprites/boid01.png";
+
+/// Asset paths of the boid sprites, with overrides per `Boid::species`.
+///
+/// Read once at startup by `load_boid_sprites`, set it before adding `BoidPlugin`.
+#[derive(Resource, Clone, Debug)]
+pub struct BoidSprites {
+    /// Sprite of species without an override.
+    pub default: String,
+    pub species: HashMap<u32, String>,
+}
+
+impl Default for BoidSprites {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_BOID_SPRITE.to_owned(),
+            species: HashMap::default(),
+        }
+    }
+}
+
+impl BoidSprites {
+    pub fn with_species(mut self, species: u32, path: impl Into<String>) -> Self {
+        self.species.insert(species, path.into());
+        self
+    }
+}
+
+/// The `BoidSprites` images, loaded up front so spawning never waits on the asset server.
+///
+/// Only present when there is an `AssetServer`, so headless apps spawn boids without sprites.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct BoidSpriteHandles {
+    pub default: Handle<Image>,
+    pub species: HashMap<u32, Handle<Image>>,
+}
+
+impl BoidSpriteHandles {
+    /// The sprite of `species`, or the default one if it has no override.
+    pub fn get(&self, species: u32) -> Handle<Image> {
+        self.species.get(&species).unwrap_or(&self.default).clone()
+    }
+}
+
+/// Loads the `BoidSprites` into `BoidSpriteHandles`, added to `PreStartup` by
+/// `BoidPlugin` so the handles exist before any spawning startup system runs.
+pub fn load_boid_sprites(
+    mut commands: Commands,
+    sprites: Res<BoidSprites>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    let Some(asset_server) = asset_server else {
+        return;
+    };
+    commands.insert_resource(BoidSpriteHandles {
+        default: asset_server.load(sprites.default.as_str()),
+        species: sprites
+            .species
+            .iter()
+            .map(|(species, path)| (*species, asset_server.load(path.as_str())))
+            .collect(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn species_without_an_override_get_the_default_sprite() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(AssetPlugin::default())
+            .add_asset::<Image>()
+            .insert_resource(BoidSprites::default().with_species(1, "sprites/boid.png"))
+            .add_systems(Startup, load_boid_sprites);
+        app.update();
+
+        let asset_server = app.world.resource::<AssetServer>();
+        let handles = app.world.resource::<BoidSpriteHandles>();
+        assert_eq!(handles.get(0), asset_server.load(DEFAULT_BOID_SPRITE));
+        assert_eq!(handles.get(1), asset_server.load("sprites/boid.png"));
+        assert_eq!(handles.get(7), handles.get(0));
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::Boid;
+
+const STATS_FONT: &str = "fonts/Hack-Regular.ttf";
+const STATS_FONT_SIZE: f32 = 18.0;
+/// Seconds between text updates, rewriting it every frame would relayout the UI every frame.
+const STATS_UPDATE_INTERVAL: f32 = 0.25;
+
+/// Shows the boid count and smoothed FPS in the top left corner, F6 toggles it.
+#[derive(Default)]
+pub struct StatsOverlayPlugin;
+
+impl Plugin for StatsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_resource::<StatsOverlay>()
+            .add_systems(Startup, spawn_stats_overlay)
+            .add_systems(Update, toggle_stats_overlay_system)
+            .add_systems(
+                Update,
+                stats_overlay_system.after(toggle_stats_overlay_system),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct StatsOverlay {
+    pub visible: bool,
+    timer: Timer,
+}
+
+impl Default for StatsOverlay {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            timer: Timer::from_seconds(STATS_UPDATE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Marks the text node written by `stats_overlay_system`.
+#[derive(Component)]
+pub struct StatsText;
+
+pub fn spawn_stats_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(STATS_FONT),
+                font_size: STATS_FONT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+        StatsText,
+    ));
+}
+
+pub fn toggle_stats_overlay_system(
+    mut overlay: ResMut<StatsOverlay>,
+    keys: Res<Input<KeyCode>>,
+    mut text_query: Query<&mut Visibility, With<StatsText>>,
+) {
+    if !keys.just_pressed(KeyCode::F6) {
+        return;
+    }
+    overlay.visible = !overlay.visible;
+    for mut visibility in text_query.iter_mut() {
+        *visibility = if overlay.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn stats_overlay_system(
+    mut overlay: ResMut<StatsOverlay>,
+    time: Res<Time>,
+    diagnostics: Res<DiagnosticsStore>,
+    boid_query: Query<(), With<Boid>>,
+    mut text_query: Query<&mut Text, With<StatsText>>,
+) {
+    if !overlay.timer.tick(time.delta()).just_finished() || !overlay.visible {
+        return;
+    }
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+    let text = stats_text(boid_query.iter().len(), fps);
+    for mut stats in text_query.iter_mut() {
+        stats.sections[0].value = text.clone();
+    }
+}
+
+fn stats_text(boid_count: usize, fps: Option<f64>) -> String {
+    match fps {
+        Some(fps) => format!("boids: {boid_count}\nfps: {fps:.0}"),
+        None => format!("boids: {boid_count}\nfps: -"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_text_before_the_first_fps_measurement() {
+        assert_eq!(stats_text(12, None), "boids: 12\nfps: -");
+        assert_eq!(stats_text(12, Some(59.6)), "boids: 12\nfps: 60");
+    }
+}
@@ -0,0 +1,505 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::flocking::{self, smooth_cohesion_target, SteeringWeights};
+use crate::{
+    cohesion_crowd_radius, current_speed, current_velocity, in_field_of_view, keep_nearest,
+    pair_separation_distance, steer_boid, steer_boid_at, Boid, BoidConfig, NeighborCache,
+    ScatterTimer, SeparationMatrix, SpeciesConfig,
+};
+
+/// What a boid knows about one of its neighbors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Neighbor {
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub speed: f32,
+    pub velocity: Vec2,
+    pub species: u32,
+    pub radius: f32,
+}
+
+/// Weighted sum of cohesion, alignment and separation for a boid at `position`, see
+/// [`flocking::steering`].
+///
+/// `weights` say how much each rule counts, the rest of the tuning comes from `config`.
+///
+/// `neighbors` must not contain the boid itself. Cohesion steers toward `boid.cohesion_target`,
+/// which the caller is expected to have updated, unless it is within `cohesion_dead_zone`.
+/// Likewise alignment steers along `boid.alignment_heading` once `alignment_smoothing` is below 1.
+/// Otherwise it only considers the boid's own species within its `alignment_radius`, separation
+/// pushes as hard as `matrix` says for each pair of species, from only the nearest
+/// `max_separation_neighbors`.
+pub fn compute_steering(
+    boid: &Boid,
+    position: Vec2,
+    neighbors: &[Neighbor],
+    weights: SteeringWeights,
+    config: &BoidConfig,
+    matrix: &SeparationMatrix,
+) -> Vec2 {
+    let cohesion_target = Some(boid.cohesion_target).filter(|target| {
+        *target != Vec2::ZERO && target.distance(position) > config.cohesion_dead_zone
+    });
+    let directions: Vec<Vec2> = neighbors
+        .iter()
+        .filter(|neighbor| neighbor.species == boid.species)
+        .filter(|neighbor| neighbor.position.distance(position) <= boid.alignment_radius())
+        .map(|neighbor| neighbor.direction)
+        .collect();
+    let smoothed = [boid.alignment_heading];
+    let directions = if config.alignment_smoothing < 1.0 && boid.alignment_heading != Vec2::ZERO {
+        &smoothed[..]
+    } else {
+        &directions[..]
+    };
+    let mut crowding: Vec<(Vec2, f32)> = neighbors
+        .iter()
+        .filter(|neighbor| {
+            neighbor.position.distance(position) <= pair_separation_distance(boid, neighbor.radius)
+        })
+        .map(|neighbor| {
+            let strength = matrix.strength(boid.species, neighbor.species, config);
+            (neighbor.position, strength)
+        })
+        .filter(|(_, strength)| *strength > 0.0)
+        .collect();
+    if let Some(count) = config.max_separation_neighbors {
+        keep_nearest(&mut crowding, position, count);
+    }
+    flocking::steering(position, cohesion_target, directions, &crowding, weights)
+}
+
+/// Steers by cohesion, alignment and separation combined into one desired velocity by
+/// [`compute_steering`], and toward the neighbors' average speed by `speed_alignment`.
+///
+/// Weights come from the boid's [`SpeciesConfig`] profile, cohesion is skipped while a
+/// [`ScatterTimer`] runs.
+///
+/// Flee, obstacle avoidance, leader following and waypoint seeking are still separate systems
+/// that add their own steering afterwards.
+pub fn steering_system(
+    neighbor_cache: Res<NeighborCache>,
+    mut boid_query: Query<(&Transform, &mut Boid, Entity), With<Boid>>,
+    config: Res<BoidConfig>,
+    scatter: Res<ScatterTimer>,
+    species: Res<SpeciesConfig>,
+    matrix: Res<SeparationMatrix>,
+) {
+    // positions come from the cache, which has them where they appear across a wrapped edge
+    let neighbor_map: HashMap<Entity, Neighbor> = boid_query
+        .iter()
+        .map(|(transform, boid, entity)| {
+            let neighbor = Neighbor {
+                position: transform.translation.xy(),
+                direction: boid.direction,
+                speed: current_speed(boid),
+                velocity: boid.velocity,
+                species: boid.species,
+                radius: boid.radius,
+            };
+            (entity, neighbor)
+        })
+        .collect();
+
+    boid_query
+        .par_iter_mut()
+        .for_each_mut(|(transform, mut boid, entity)| {
+            let position = transform.translation.xy();
+            let neighbors: Vec<Neighbor> = neighbor_cache
+                .get(entity)
+                .iter()
+                .filter(|(pos, _)| in_field_of_view(&boid, position, *pos))
+                .filter_map(|(pos, option)| {
+                    let other = option.filter(|other| *other != entity)?;
+                    Some(Neighbor {
+                        position: *pos,
+                        ..*neighbor_map.get(&other)?
+                    })
+                })
+                .collect();
+
+            // flockmates within the radius of one rule
+            let own_species = boid.species;
+            let neighbors = &neighbors;
+            let within = move |radius: f32| {
+                neighbors.iter().filter(move |neighbor| {
+                    neighbor.species == own_species
+                        && neighbor.position.distance(position) <= radius
+                })
+            };
+            let (positions, velocities): (Vec<Vec2>, Vec<Vec2>) = within(boid.cohesion_radius())
+                .map(|neighbor| (neighbor.position, neighbor.velocity))
+                .unzip();
+            let (speeds, directions): (Vec<f32>, Vec<Vec2>) = within(boid.alignment_radius())
+                .map(|neighbor| (neighbor.speed, neighbor.direction))
+                .unzip();
+            boid.alignment_heading = flocking::smooth_alignment(
+                boid.alignment_heading,
+                flocking::average_direction(&directions),
+                config.alignment_smoothing,
+            );
+            let lookahead = config.cohesion_lookahead();
+            let predicted = flocking::predicted_positions(&positions, &velocities, lookahead);
+            let average = flocking::weighted_centroid(
+                position,
+                &predicted,
+                config.cohesion_weighting,
+                cohesion_crowd_radius(&boid),
+            );
+            boid.cohesion_target = smooth_cohesion_target(
+                boid.cohesion_target,
+                average.unwrap_or(Vec2::ZERO),
+                config.cohesion_smoothing,
+                boid.view_distance,
+            );
+
+            if let Some(average_speed) = flocking::average_speed(&speeds) {
+                let heading = current_velocity(&boid);
+                steer_boid_at(&mut boid, heading, average_speed, config.speed_alignment);
+            }
+
+            let mut weights = species.weights(boid.species, &config);
+            if scatter.active() {
+                weights.cohesion = 0.0;
+            }
+            let steering = compute_steering(&boid, position, neighbors, weights, &config, &matrix);
+            let weight = boid.rotation_speed * steering.length();
+            steer_boid(&mut boid, steering, weight);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BOID_SPRITE_RADIUS;
+
+    fn neighbor(position: Vec2, direction: Vec2) -> Neighbor {
+        Neighbor {
+            position,
+            direction,
+            speed: 20.0,
+            velocity: direction * 20.0,
+            species: 0,
+            radius: BOID_SPRITE_RADIUS,
+        }
+    }
+
+    #[test]
+    fn weights_add_up() {
+        let boid = Boid {
+            cohesion_target: Vec2::new(10.0, 0.0),
+            ..default()
+        };
+        let neighbors = [neighbor(Vec2::new(5.0, 0.0), Vec2::Y)];
+        let config = BoidConfig {
+            cohesion: 1.0,
+            alignment: 0.5,
+            separation: 2.0,
+            ..default()
+        };
+        // cohesion pulls +x, separation pushes -x twice as hard, alignment points +y
+        let steering = compute_steering(
+            &boid,
+            Vec2::ZERO,
+            &neighbors,
+            config.steering_weights(),
+            &config,
+            &SeparationMatrix::default(),
+        );
+        assert!(
+            steering.abs_diff_eq(Vec2::new(-1.0, 0.5), 1e-5),
+            "{steering}"
+        );
+    }
+
+    #[test]
+    fn alignment_only_matches_neighbors_within_its_radius() {
+        let config = BoidConfig {
+            cohesion: 0.0,
+            alignment: 1.0,
+            separation: 0.0,
+            ..default()
+        };
+        let neighbors = [
+            neighbor(Vec2::new(10.0, 0.0), Vec2::Y),
+            neighbor(Vec2::new(0.0, 40.0), Vec2::NEG_X),
+        ];
+        // both are within the view distance of 50
+        let wide = compute_steering(
+            &Boid::default(),
+            Vec2::ZERO,
+            &neighbors,
+            config.steering_weights(),
+            &config,
+            &SeparationMatrix::default(),
+        );
+        assert!(
+            wide.abs_diff_eq(Vec2::new(-1.0, 1.0).normalize(), 1e-5),
+            "{wide}"
+        );
+
+        let narrow = Boid {
+            alignment_radius: Some(20.0),
+            ..default()
+        };
+        let steering = compute_steering(
+            &narrow,
+            Vec2::ZERO,
+            &neighbors,
+            config.steering_weights(),
+            &config,
+            &SeparationMatrix::default(),
+        );
+        assert_eq!(steering, Vec2::Y);
+    }
+
+    #[test]
+    fn no_cohesion_inside_the_dead_zone() {
+        let config = BoidConfig {
+            cohesion: 1.0,
+            alignment: 0.0,
+            separation: 0.0,
+            cohesion_dead_zone: 2.0,
+            ..default()
+        };
+        let at = |offset: Vec2| {
+            let boid = Boid {
+                cohesion_target: Vec2::new(100.0, 100.0) + offset,
+                ..default()
+            };
+            compute_steering(
+                &boid,
+                Vec2::new(100.0, 100.0),
+                &[],
+                config.steering_weights(),
+                &config,
+                &SeparationMatrix::default(),
+            )
+        };
+        assert_eq!(at(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(at(Vec2::new(1.5, 0.0)), Vec2::ZERO);
+        assert_eq!(at(Vec2::new(3.0, 0.0)), Vec2::X);
+    }
+
+    #[test]
+    fn no_neighbors_and_no_target_means_no_steering() {
+        let boid = Boid::default();
+        let steering = compute_steering(
+            &boid,
+            Vec2::ZERO,
+            &[],
+            BoidConfig::default().steering_weights(),
+            &BoidConfig::default(),
+            &SeparationMatrix::default(),
+        );
+        assert_eq!(steering, Vec2::ZERO);
+    }
+
+    #[test]
+    fn the_separation_matrix_can_be_one_sided() {
+        const RED: u32 = 0;
+        const BLUE: u32 = 1;
+        let config = BoidConfig {
+            cohesion: 0.0,
+            alignment: 0.0,
+            separation: 1.0,
+            ..default()
+        };
+        let matrix = SeparationMatrix::default()
+            .with_strength(RED, BLUE, 0.5)
+            .with_strength(BLUE, BLUE, 0.0);
+        let red = Boid {
+            species: RED,
+            ..default()
+        };
+        let blue = Boid {
+            species: BLUE,
+            ..default()
+        };
+        let neighbor_of = |species| Neighbor {
+            species,
+            ..neighbor(Vec2::new(5.0, 0.0), Vec2::Y)
+        };
+
+        // red avoids blue at half strength, blue ignores red and even its own kind
+        assert_eq!(
+            compute_steering(
+                &red,
+                Vec2::ZERO,
+                &[neighbor_of(BLUE)],
+                config.steering_weights(),
+                &config,
+                &matrix
+            ),
+            Vec2::NEG_X * 0.5
+        );
+        assert_eq!(
+            compute_steering(
+                &blue,
+                Vec2::ZERO,
+                &[neighbor_of(RED)],
+                config.steering_weights(),
+                &config,
+                &matrix
+            ),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            compute_steering(
+                &blue,
+                Vec2::ZERO,
+                &[neighbor_of(BLUE)],
+                config.steering_weights(),
+                &config,
+                &matrix
+            ),
+            Vec2::ZERO
+        );
+        // pairs left out keep the default of same species only
+        assert_eq!(
+            compute_steering(
+                &red,
+                Vec2::ZERO,
+                &[neighbor_of(RED)],
+                config.steering_weights(),
+                &config,
+                &matrix
+            ),
+            Vec2::NEG_X
+        );
+    }
+
+    #[test]
+    fn other_species_only_separate_when_configured() {
+        let boid = Boid::default();
+        let stranger = Neighbor {
+            species: 1,
+            ..neighbor(Vec2::new(5.0, 0.0), Vec2::Y)
+        };
+        let config = BoidConfig {
+            cohesion: 0.0,
+            alignment: 1.0,
+            separation: 1.0,
+            separate_across_species: false,
+            ..default()
+        };
+        assert_eq!(
+            compute_steering(
+                &boid,
+                Vec2::ZERO,
+                &[stranger],
+                config.steering_weights(),
+                &config,
+                &SeparationMatrix::default()
+            ),
+            Vec2::ZERO
+        );
+
+        let config = BoidConfig {
+            separate_across_species: true,
+            ..config
+        };
+        assert_eq!(
+            compute_steering(
+                &boid,
+                Vec2::ZERO,
+                &[stranger],
+                config.steering_weights(),
+                &config,
+                &SeparationMatrix::default()
+            ),
+            Vec2::NEG_X
+        );
+    }
+
+    #[test]
+    fn only_the_nearest_separation_neighbors_push() {
+        let config = BoidConfig {
+            cohesion: 0.0,
+            alignment: 0.0,
+            separation: 1.0,
+            ..default()
+        };
+        // one close on the right, three a little further up, all within 4 + 4 + 2
+        let neighbors = [
+            neighbor(Vec2::new(0.0, 5.0), Vec2::Y),
+            neighbor(Vec2::new(2.0, 0.0), Vec2::Y),
+            neighbor(Vec2::new(0.0, 6.0), Vec2::Y),
+            neighbor(Vec2::new(0.0, 7.0), Vec2::Y),
+        ];
+        let all = compute_steering(
+            &Boid::default(),
+            Vec2::ZERO,
+            &neighbors,
+            config.steering_weights(),
+            &config,
+            &SeparationMatrix::default(),
+        );
+        assert!(all.x < 0.0 && all.y < 0.0, "{all}");
+
+        let nearest = |count| {
+            let config = BoidConfig {
+                max_separation_neighbors: Some(count),
+                ..config.clone()
+            };
+            compute_steering(
+                &Boid::default(),
+                Vec2::ZERO,
+                &neighbors,
+                config.steering_weights(),
+                &config,
+                &SeparationMatrix::default(),
+            )
+        };
+        assert_eq!(nearest(1), Vec2::NEG_X);
+        let two = nearest(2);
+        assert!(
+            two.y < 0.0 && two.y > all.y,
+            "{two} should lean less down than {all}"
+        );
+        assert!(nearest(4).abs_diff_eq(all, 1e-6));
+        assert_eq!(nearest(0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn large_neighbors_are_separated_from_further_away() {
+        let config = BoidConfig {
+            cohesion: 0.0,
+            alignment: 0.0,
+            separation: 1.0,
+            ..default()
+        };
+        let small = Boid::default();
+        let small_neighbor = neighbor(Vec2::new(15.0, 0.0), Vec2::Y);
+        let large_neighbor = Neighbor {
+            radius: 12.0,
+            ..small_neighbor
+        };
+
+        // two small boids 15 apart are outside 4 + 4 + 2, a large one reaches 4 + 12 + 2
+        assert_eq!(
+            compute_steering(
+                &small,
+                Vec2::ZERO,
+                &[small_neighbor],
+                config.steering_weights(),
+                &config,
+                &SeparationMatrix::default()
+            ),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            compute_steering(
+                &small,
+                Vec2::ZERO,
+                &[large_neighbor],
+                config.steering_weights(),
+                &config,
+                &SeparationMatrix::default()
+            ),
+            Vec2::NEG_X
+        );
+    }
+}
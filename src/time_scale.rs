@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// How much `+` or `-` changes the `TimeScale` per press.
+const TIME_SCALE_STEP: f32 = 0.25;
+const MAX_TIME_SCALE: f32 = 8.0;
+
+/// Multiplies the simulated time of every fixed step, 0.5 runs the flock at half speed and 0
+/// freezes it.
+///
+/// Only the length of a step changes, not how often steps run, so the spatial tree and neighbor
+/// cache keep rebuilding once per frame at any scale. Negative scales count as zero.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl TimeScale {
+    pub fn get(self) -> f32 {
+        self.0.max(0.0)
+    }
+}
+
+/// The simulated length of the current fixed step, `FixedTime::period` times the `TimeScale`.
+///
+/// Movement and steering use it in place of the period so they all speed up and slow down
+/// together.
+#[derive(SystemParam)]
+pub struct SimulationStep<'w> {
+    time: Res<'w, FixedTime>,
+    scale: Res<'w, TimeScale>,
+}
+
+impl SimulationStep<'_> {
+    pub fn delta(&self) -> Duration {
+        self.time.period.mul_f32(self.scale.get())
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.time.period.as_secs_f32() * self.scale.get()
+    }
+}
+
+/// `+` speeds the simulation up and `-` slows it down, down to a standstill at zero.
+pub fn adjust_time_scale_system(mut scale: ResMut<TimeScale>, keys: Res<Input<KeyCode>>) {
+    let faster = keys.any_just_pressed([KeyCode::Plus, KeyCode::Equals, KeyCode::NumpadAdd]);
+    let slower = keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]);
+    let change = match (faster, slower) {
+        (true, false) => TIME_SCALE_STEP,
+        (false, true) => -TIME_SCALE_STEP,
+        _ => return,
+    };
+    scale.0 = (scale.get() + change).clamp(0.0, MAX_TIME_SCALE);
+    info!("time scale {:.2}", scale.0);
+}
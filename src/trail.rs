@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{Boid, DebugDraw, SimState};
+
+/// Segments longer than this are skipped, they come from a boid wrapping around the screen.
+const TRAIL_JUMP_DISTANCE: f32 = 100.0;
+const TRAIL_COLOR: Color = Color::WHITE;
+
+/// Draws a fading line behind every boid, needs `DebugDrawPlugin`.
+#[derive(Default)]
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrailSettings>()
+            .add_systems(Update, attach_trail_system)
+            .add_systems(Update, trail_system.after(attach_trail_system));
+    }
+}
+
+#[derive(Resource)]
+pub struct TrailSettings {
+    pub enabled: bool,
+    /// Number of positions kept by trails attached from now on.
+    pub max_len: usize,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_len: 30,
+        }
+    }
+}
+
+/// The last `max_len` positions of a boid, oldest first.
+#[derive(Component)]
+pub struct Trail {
+    pub points: VecDeque<Vec2>,
+    pub max_len: usize,
+}
+
+impl Trail {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            points: VecDeque::with_capacity(max_len + 1),
+            max_len,
+        }
+    }
+
+    fn push(&mut self, point: Vec2) {
+        self.points.push_back(point);
+        while self.points.len() > self.max_len {
+            self.points.pop_front();
+        }
+    }
+}
+
+/// Gives every boid without one a `Trail` using the current `TrailSettings::max_len`.
+pub fn attach_trail_system(
+    mut commands: Commands,
+    settings: Res<TrailSettings>,
+    boid_query: Query<Entity, (With<Boid>, Without<Trail>)>,
+) {
+    for entity in boid_query.iter() {
+        commands.entity(entity).insert(Trail::new(settings.max_len));
+    }
+}
+
+/// Records the boid positions and draws each trail, fading toward the tail.
+///
+/// Every segment is redrawn each frame and lasts about a frame, so a trail disappears the frame
+/// after its boid is despawned or trails are turned off. While paused no points are recorded.
+pub fn trail_system(
+    mut lines: DebugDraw,
+    settings: Res<TrailSettings>,
+    sim_state: Res<SimState>,
+    mut trail_query: Query<(&Transform, &mut Trail)>,
+) {
+    if !settings.enabled {
+        for (_, mut trail) in trail_query.iter_mut() {
+            trail.points.clear();
+        }
+        return;
+    }
+
+    let running = !sim_state.paused || sim_state.step;
+    for (transform, mut trail) in trail_query.iter_mut() {
+        if running {
+            trail.push(transform.translation.xy());
+        }
+
+        let len = trail.points.len();
+        for (i, (start, end)) in trail
+            .points
+            .iter()
+            .zip(trail.points.iter().skip(1))
+            .enumerate()
+        {
+            if start.distance(*end) > TRAIL_JUMP_DISTANCE {
+                continue;
+            }
+            lines.line_gradient(
+                start.extend(0.0),
+                end.extend(0.0),
+                TRAIL_COLOR.with_a(trail_alpha(i, len)),
+                TRAIL_COLOR.with_a(trail_alpha(i + 1, len)),
+            );
+        }
+    }
+}
+
+/// Toggles trails with `T`.
+pub fn toggle_trails_system(mut settings: ResMut<TrailSettings>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::T) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+/// Opacity of the `index`th point of a trail with `len` points, 0 at the tail and 1 at the boid.
+fn trail_alpha(index: usize, len: usize) -> f32 {
+    if len <= 1 {
+        return 1.0;
+    }
+    index as f32 / (len - 1) as f32
+}
@@ -0,0 +1,32 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{get_random_direction, Boid, NeighborCache, RngResource};
+
+/// Neighbors closer than this count as sitting on the same spot.
+const STACKED_DISTANCE: f32 = 0.01;
+/// How far `unstack_system` moves a stacked boid.
+const UNSTACK_NUDGE: f32 = 0.5;
+
+/// Moves boids that sit exactly on top of another one a tiny step in a random direction.
+///
+/// Separation has no direction to push two coincident boids in, e.g. after several clicks on the
+/// same spot, so without the nudge they would fly on as one forever. Of each stacked pair only
+/// the boid with the higher `Entity` moves, and the direction is drawn from `RngResource` so
+/// seeded runs repeat.
+pub fn unstack_system(
+    neighbor_cache: Res<NeighborCache>,
+    mut boid_query: Query<(Entity, &mut Transform), With<Boid>>,
+    mut rng: ResMut<RngResource>,
+) {
+    for (entity, mut transform) in boid_query.iter_mut() {
+        let position = transform.translation.xy();
+        let stacked = neighbor_cache.get(entity).iter().any(|(pos, other)| {
+            other.is_some_and(|other| other < entity) && pos.distance(position) < STACKED_DISTANCE
+        });
+        if stacked {
+            let nudge = get_random_direction(&mut rng.0) * UNSTACK_NUDGE;
+            transform.translation += nudge.extend(0.0);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{current_velocity, rotate_vector, Boid, BoidConfig, RngResource};
+
+/// Largest angle, in radians, the wander target is turned away from the current heading.
+const WANDER_ANGLE: f32 = std::f32::consts::FRAC_PI_2;
+
+/// Nudges every boid toward a random heading close to its own, weighted by `wander`.
+///
+/// This is a small turn on top of the other steering, not a replacement of the direction, so it
+/// only roughens perfectly aligned flocks. Only the sideways part of the pull is kept, the random
+/// targets average out to a slower velocity and would otherwise brake every boid. Draws from
+/// `RngResource` so seeded runs repeat.
+pub fn wander_system(
+    mut boid_query: Query<&mut Boid>,
+    mut rng: ResMut<RngResource>,
+    config: Res<BoidConfig>,
+) {
+    if config.wander <= 0.0 {
+        return;
+    }
+    for mut boid in boid_query.iter_mut() {
+        let angle = rng.gen_range(-WANDER_ANGLE..=WANDER_ANGLE);
+        let velocity = current_velocity(&boid);
+        let heading = velocity.try_normalize().unwrap_or(boid.direction);
+        let desired = rotate_vector(heading, angle) * boid.speed;
+        let pull = (desired - velocity) * boid.rotation_speed * config.wander;
+        boid.steering += pull.reject_from_normalized(heading);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wandering_turns_without_braking() {
+        let mut app = App::new();
+        app.insert_resource(RngResource::seeded(0))
+            .init_resource::<BoidConfig>()
+            .add_systems(Update, wander_system);
+        let boid = app.world.spawn(Boid::default()).id();
+        app.update();
+
+        let boid = app.world.get::<Boid>(boid).unwrap();
+        assert_ne!(boid.steering, Vec2::ZERO);
+        assert!(
+            boid.steering.dot(boid.direction).abs() < 1e-4,
+            "{}",
+            boid.steering
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{get_random_direction, Boid, RngResource, SimulationStep};
+
+/// A boid that stays within this distance of where it stopped counts as stuck.
+const STALL_DISTANCE: f32 = 0.5;
+/// Seconds of simulated time a boid has to stay stuck before `watchdog_system` resets it.
+const STALL_SECONDS: f32 = 3.0;
+
+/// Where `watchdog_system` last saw a boid move from and how long it has stayed there.
+#[derive(Clone, Copy)]
+pub struct Stall {
+    anchor: Vec2,
+    seconds: f32,
+}
+
+/// Resets boids whose heading went bad or that stopped moving, with a fresh random direction.
+///
+/// A last line of defense behind the NaN guards in the steering math: a non-finite or zero
+/// `direction` or `velocity` would otherwise keep the boid frozen, or poison every neighbor that
+/// averages over it. Each recovery is logged as a warning so the bug that caused it still shows.
+pub fn watchdog_system(
+    mut boid_query: Query<(Entity, &Transform, &mut Boid)>,
+    mut stalls: Local<HashMap<Entity, Stall>>,
+    mut rng: ResMut<RngResource>,
+    time: SimulationStep,
+) {
+    let delta_seconds = time.delta_seconds();
+    let mut seen = HashMap::with_capacity(stalls.len());
+    for (entity, transform, mut boid) in boid_query.iter_mut() {
+        let position = transform.translation.xy();
+        let broken = !boid.direction.is_finite()
+            || boid.direction == Vec2::ZERO
+            || !boid.velocity.is_finite()
+            || !boid.steering.is_finite();
+        let mut stall = stalls.get(&entity).copied().unwrap_or(Stall {
+            anchor: position,
+            seconds: 0.0,
+        });
+        if position.distance(stall.anchor) > STALL_DISTANCE {
+            stall = Stall {
+                anchor: position,
+                seconds: 0.0,
+            };
+        } else {
+            stall.seconds += delta_seconds;
+        }
+        let stalled = stall.seconds >= STALL_SECONDS;
+        if broken || stalled {
+            if broken {
+                warn!(
+                    "{entity:?} had direction {}, velocity {} and steering {}, resetting it",
+                    boid.direction, boid.velocity, boid.steering
+                );
+            } else {
+                warn!(
+                    "{entity:?} has not moved from {position} in {:.1}s, resetting it",
+                    stall.seconds
+                );
+            }
+            boid.direction = get_random_direction(&mut rng.0);
+            boid.velocity = boid.direction * boid.speed;
+            boid.angular_velocity = 0.0;
+            boid.steering = Vec2::ZERO;
+            if !boid.cohesion_target.is_finite() {
+                boid.cohesion_target = Vec2::ZERO;
+            }
+            if !boid.alignment_heading.is_finite() {
+                boid.alignment_heading = Vec2::ZERO;
+            }
+            if !boid.stamina.is_finite() {
+                boid.stamina = 1.0;
+            }
+            stall = Stall {
+                anchor: position,
+                seconds: 0.0,
+            };
+        }
+        seen.insert(entity, stall);
+    }
+    // dropping the old map forgets despawned boids
+    *stalls = seen;
+}
@@ -0,0 +1,70 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+use crate::{arrive, draw_x, Boid, BoidConfig, DebugDraw, WorldCursor};
+
+/// The point the whole flock is heading for, removed once the flock's centroid reaches it.
+#[derive(Resource, Clone, Copy)]
+pub struct Waypoint {
+    pub pos: Vec2,
+    pub arrive_radius: f32,
+}
+
+impl Waypoint {
+    pub fn new(pos: Vec2) -> Self {
+        Self {
+            pos,
+            arrive_radius: 40.0,
+        }
+    }
+}
+
+/// Places the waypoint at the cursor on middle click, replacing the previous one.
+pub fn set_waypoint_system(
+    mut commands: Commands,
+    cursor: WorldCursor,
+    buttons: Res<Input<MouseButton>>,
+) {
+    if buttons.just_released(MouseButton::Middle) {
+        if let Some(mouse_pos) = cursor.position() {
+            commands.insert_resource(Waypoint::new(mouse_pos));
+        }
+    }
+}
+
+/// Steers every boid toward the waypoint, if there is one, slowing down within
+/// `slowing_radius` of it.
+pub fn seek_waypoint_system(
+    mut commands: Commands,
+    waypoint: Option<Res<Waypoint>>,
+    mut boid_query: Query<(&Transform, &mut Boid)>,
+    config: Res<BoidConfig>,
+) {
+    let Some(waypoint) = waypoint else { return };
+
+    let mut centroid = Vec2::ZERO;
+    let mut count = 0.0;
+    for (transform, mut boid) in boid_query.iter_mut() {
+        let position = transform.translation.xy();
+        centroid += position;
+        count += 1.0;
+
+        let weight = boid.rotation_speed * config.seek;
+        arrive(
+            &mut boid,
+            waypoint.pos - position,
+            config.slowing_radius,
+            weight,
+        );
+    }
+
+    if count > 0.0 && (centroid / count).distance(waypoint.pos) <= waypoint.arrive_radius {
+        commands.remove_resource::<Waypoint>();
+    }
+}
+
+pub fn draw_waypoint_system(waypoint: Option<Res<Waypoint>>, mut lines: DebugDraw) {
+    if let Some(waypoint) = waypoint {
+        draw_x(&mut lines, waypoint.pos);
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+/// Carries every boid along without turning it, so the heading stays driven by flocking.
+///
+/// Both parts default to nothing, set `velocity` for a uniform wind and `field` for one that
+/// varies across the world.
+#[derive(Resource, Default)]
+pub struct Wind {
+    pub velocity: Vec2,
+    /// Extra wind sampled at each boid's position.
+    pub field: Option<Box<dyn Fn(Vec2) -> Vec2 + Send + Sync>>,
+}
+
+impl Wind {
+    /// Wind velocity at `position`.
+    pub fn at(&self, position: Vec2) -> Vec2 {
+        match &self.field {
+            Some(field) => self.velocity + field(position),
+            None => self.velocity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_adds_to_uniform_wind() {
+        let wind = Wind {
+            velocity: Vec2::new(1.0, 0.0),
+            field: Some(Box::new(|position| Vec2::new(0.0, position.x))),
+        };
+        assert_eq!(wind.at(Vec2::new(3.0, 0.0)), Vec2::new(1.0, 3.0));
+        assert_eq!(Wind::default().at(Vec2::ONE), Vec2::ZERO);
+    }
+}
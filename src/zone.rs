@@ -0,0 +1,110 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::Boid;
+
+/// A region of the world, in world coordinates, that boids are reported entering and leaving.
+///
+/// The entity's own `Transform` is ignored, zones may overlap.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Zone {
+    pub rect: Rect,
+}
+
+/// Sent by `zone_detection_system` when `boid` moves into `zone`.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoidEnteredZone {
+    pub boid: Entity,
+    pub zone: Entity,
+}
+
+/// Sent by `zone_detection_system` when `boid` moves out of `zone`.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoidLeftZone {
+    pub boid: Entity,
+    pub zone: Entity,
+}
+
+/// Compares which zones each boid is in with the step before and sends an event for every change.
+///
+/// A boid in several overlapping zones gets an event for each of them. A boid spawned inside a zone
+/// enters it on its first step, despawning a boid or a zone forgets it without a `BoidLeftZone`.
+pub fn zone_detection_system(
+    boid_query: Query<(Entity, &Transform), With<Boid>>,
+    zone_query: Query<(Entity, &Zone)>,
+    mut inside: Local<HashSet<(Entity, Entity)>>,
+    mut entered: EventWriter<BoidEnteredZone>,
+    mut left: EventWriter<BoidLeftZone>,
+) {
+    let mut now_inside = HashSet::with_capacity(inside.len());
+    for (zone_entity, zone) in zone_query.iter() {
+        for (boid, transform) in boid_query.iter() {
+            if zone.rect.contains(transform.translation.xy()) {
+                now_inside.insert((boid, zone_entity));
+            }
+        }
+    }
+    for &(boid, zone) in now_inside.difference(&inside) {
+        entered.send(BoidEnteredZone { boid, zone });
+    }
+    for &(boid, zone) in inside.difference(&now_inside) {
+        if boid_query.contains(boid) && zone_query.contains(zone) {
+            left.send(BoidLeftZone { boid, zone });
+        }
+    }
+    *inside = now_inside;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::event::ManualEventReader;
+
+    fn zone_app() -> App {
+        let mut app = App::new();
+        app.add_event::<BoidEnteredZone>()
+            .add_event::<BoidLeftZone>()
+            .add_systems(Update, zone_detection_system);
+        app
+    }
+
+    #[test]
+    fn crossing_into_a_zone_enters_it_once() {
+        let mut app = zone_app();
+        let zone = app
+            .world
+            .spawn(Zone {
+                rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+            })
+            .id();
+        // a second zone overlapping the first only on its right half
+        let overlapping = app
+            .world
+            .spawn(Zone {
+                rect: Rect::new(5.0, 0.0, 20.0, 10.0),
+            })
+            .id();
+        let boid = app
+            .world
+            .spawn((Transform::from_xyz(-5.0, 5.0, 0.0), Boid::default()))
+            .id();
+
+        let mut entered_reader = ManualEventReader::<BoidEnteredZone>::default();
+        let mut left_reader = ManualEventReader::<BoidLeftZone>::default();
+        let mut entered = Vec::new();
+        let mut left = Vec::new();
+        for x in [-5.0, 2.0, 3.0, 4.0, 8.0, 9.0, 30.0] {
+            app.world.get_mut::<Transform>(boid).unwrap().translation.x = x;
+            app.update();
+            let events = app.world.resource::<Events<BoidEnteredZone>>();
+            entered.extend(entered_reader.iter(events).map(|event| (event.zone, x)));
+            let events = app.world.resource::<Events<BoidLeftZone>>();
+            left.extend(left_reader.iter(events).map(|event| (event.zone, x)));
+        }
+
+        assert_eq!(entered, [(zone, 2.0), (overlapping, 8.0)]);
+        left.sort_by_key(|(zone, _)| *zone);
+        assert_eq!(left, [(zone, 30.0), (overlapping, 30.0)]);
+    }
+}